@@ -49,6 +49,10 @@ fn main() {
                           "EGL_MESA_platform_gbm",
                           "EGL_EXT_platform_wayland",
                           "EGL_EXT_platform_device",
+                          "EGL_MESA_platform_surfaceless",
+                          "EGL_KHR_surfaceless_context",
+                          "EGL_EXT_pixel_format_float",
+                          "EGL_KHR_context_flush_control",
                       ])
             .write_bindings(gl_generator::StructGenerator, &mut file).unwrap();
     }
@@ -87,6 +91,10 @@ fn main() {
                           "EGL_MESA_platform_gbm",
                           "EGL_EXT_platform_wayland",
                           "EGL_EXT_platform_device",
+                          "EGL_MESA_platform_surfaceless",
+                          "EGL_KHR_surfaceless_context",
+                          "EGL_EXT_pixel_format_float",
+                          "EGL_KHR_context_flush_control",
                       ])
             .write_bindings(gl_generator::StructGenerator, &mut file).unwrap();
     }
@@ -106,6 +114,10 @@ fn main() {
                           "EGL_MESA_platform_gbm",
                           "EGL_EXT_platform_wayland",
                           "EGL_EXT_platform_device",
+                          "EGL_MESA_platform_surfaceless",
+                          "EGL_KHR_surfaceless_context",
+                          "EGL_EXT_pixel_format_float",
+                          "EGL_KHR_context_flush_control",
                       ])
             .write_bindings(gl_generator::StaticStructGenerator, &mut file).unwrap();
     }
@@ -125,6 +137,10 @@ fn main() {
                           "EGL_MESA_platform_gbm",
                           "EGL_EXT_platform_wayland",
                           "EGL_EXT_platform_device",
+                          "EGL_MESA_platform_surfaceless",
+                          "EGL_KHR_surfaceless_context",
+                          "EGL_EXT_pixel_format_float",
+                          "EGL_KHR_context_flush_control",
                       ])
             .write_bindings(gl_generator::StaticStructGenerator, &mut file).unwrap();
 
@@ -139,6 +155,11 @@ fn main() {
             .write_bindings(gl_generator::GlobalGenerator, &mut file).unwrap();
     }
 
+    // OpenGL functions that glutin calls itself, for example to manage offscreen framebuffers
+    let mut file = File::create(&dest.join("internal_gl_bindings.rs")).unwrap();
+    Registry::new(Api::Gl, (4, 5), Profile::Core, Fallbacks::All, [])
+            .write_bindings(gl_generator::StructGenerator, &mut file).unwrap();
+
     // TODO: only build the bindings below if we run tests/examples
 
     let mut file = File::create(&dest.join("test_gl_bindings.rs")).unwrap();
@@ -9,7 +9,10 @@ use CreationError::{self, OsError};
 use winit;
 
 use Api;
+use ConfigDescriptor;
 use ContextError;
+use ContextInfo;
+use Extensions;
 use GlAttributes;
 use PixelFormat;
 use PixelFormatRequirements;
@@ -46,6 +49,12 @@ impl android_glue::SyncEventHandler for AndroidSyncEventHandler {
     }
 }
 
+/// See the docs in the crate root file.
+#[inline]
+pub fn available_configs(_: &winit::EventsLoop) -> Result<Vec<ConfigDescriptor>, CreationError> {
+    Err(CreationError::NotSupported)
+}
+
 impl Context {
     pub fn new(
         window_builder: winit::WindowBuilder,
@@ -62,7 +71,8 @@ impl Context {
         }
         let egl = egl::ffi::egl::Egl;
         let native_display = egl::NativeDisplay::Android;
-        let context = try!(EglContext::new(egl, pf_reqs, &gl_attr, native_display)
+        let context = try!(EglContext::new(egl, pf_reqs, &gl_attr, native_display,
+                                           egl::SurfaceType::Window)
             .and_then(|p| p.finish(native_window as *const _)));
         let ctx = Arc::new(AndroidContext {
             egl_context: context,
@@ -94,6 +104,15 @@ impl Context {
         Ok((window, context))
     }
 
+    /// See the docs in the crate root file.
+    #[inline]
+    pub fn recreate(&self, _: &winit::Window, _: &GlAttributes<&Context>)
+                    -> Result<Self, CreationError>
+    {
+        // the lifecycle callbacks of the activity hold the current context
+        Err(CreationError::NotSupported)
+    }
+
     #[inline]
     pub unsafe fn make_current(&self) -> Result<(), ContextError> {
         if !self.0.stopped.get() {
@@ -134,6 +153,21 @@ impl Context {
         self.0.egl_context.get_pixel_format()
     }
 
+    #[inline]
+    pub fn get_context_info(&self) -> ContextInfo {
+        self.0.egl_context.get_context_info()
+    }
+
+    #[inline]
+    pub fn get_platform_extensions(&self) -> &Extensions {
+        self.0.egl_context.get_extensions()
+    }
+
+    #[inline]
+    pub fn get_vsync(&self) -> Option<bool> {
+        None
+    }
+
     #[inline]
     pub unsafe fn raw_handle(&self) -> egl::ffi::EGLContext {
         self.0.egl_context.raw_handle()
@@ -149,6 +183,14 @@ unsafe impl Send for HeadlessContext {}
 unsafe impl Sync for HeadlessContext {}
 
 impl HeadlessContext {
+    /// See the docs in the crate root file.
+    #[inline]
+    pub fn available_configs(_: &PlatformSpecificHeadlessBuilderAttributes)
+                             -> Result<Vec<ConfigDescriptor>, CreationError>
+    {
+        Err(CreationError::NotSupported)
+    }
+
     /// See the docs in the crate root file.
     pub fn new(
         dimensions: (u32, u32),
@@ -161,11 +203,31 @@ impl HeadlessContext {
         let context = try!(EglContext::new(egl::ffi::egl::Egl,
                                            pf_reqs,
                                            &gl_attr,
-                                           egl::NativeDisplay::Android));
+                                           egl::NativeDisplay::Android,
+                                           egl::SurfaceType::PBuffer));
         let context = try!(context.finish_pbuffer(dimensions));     // TODO:
         Ok(HeadlessContext(context))
     }
 
+    /// Creates a pbuffer context that shares its objects with the context of a window.
+    /// `gl_attr.sharing` must be the context of the window.
+    pub fn new_shared_with_window(
+        _: &winit::Window,
+        dimensions: (u32, u32),
+        pf_reqs: &PixelFormatRequirements,
+        gl_attr: &GlAttributes<&Context>,
+    ) -> Result<Self, CreationError>
+    {
+        let gl_attr = gl_attr.clone().map_sharing(|c| &c.0.egl_context);
+        let context = try!(EglContext::new(egl::ffi::egl::Egl,
+                                           pf_reqs,
+                                           &gl_attr,
+                                           egl::NativeDisplay::Android,
+                                           egl::SurfaceType::PBuffer));
+        let context = try!(context.finish_pbuffer(dimensions));
+        Ok(HeadlessContext(context))
+    }
+
     #[inline]
     pub unsafe fn make_current(&self) -> Result<(), ContextError> {
         self.0.make_current()
@@ -196,8 +258,28 @@ impl HeadlessContext {
         self.0.get_pixel_format()
     }
 
+    #[inline]
+    pub fn get_context_info(&self) -> ContextInfo {
+        self.0.get_context_info()
+    }
+
+    #[inline]
+    pub fn get_platform_extensions(&self) -> &Extensions {
+        self.0.get_extensions()
+    }
+
     #[inline]
     pub unsafe fn raw_handle(&self) -> egl::ffi::EGLContext {
         self.0.raw_handle()
     }
+
+    #[inline]
+    pub fn resize(&self, width: u32, height: u32) -> Result<(), CreationError> {
+        unsafe { self.0.resize_pbuffer((width, height)) }
+    }
+
+    #[inline]
+    pub unsafe fn resolve_framebuffer(&self) -> u32 {
+        0
+    }
 }
@@ -90,7 +90,8 @@ impl Context {
             let width = (self.libcaca.caca_get_canvas_width)(canvas);
             let height = (self.libcaca.caca_get_canvas_height)(canvas);
 
-            let buffer = self.opengl.get_framebuffer().chunks(self.opengl.get_dimensions().0 as usize)
+            let framebuffer = self.opengl.get_framebuffer();
+            let buffer = framebuffer.data.chunks(framebuffer.dimensions.0 as usize)
                                     .flat_map(|i| i.iter().cloned()).rev().collect::<Vec<u32>>();
 
             (self.libcaca.caca_dither_bitmap)(canvas, 0, 0, width as libc::c_int,
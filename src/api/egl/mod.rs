@@ -2,9 +2,16 @@
            target_os = "dragonfly", target_os = "freebsd", target_os = "openbsd"))]
 #![allow(unused_variables)]
 
+use ConfigBackend;
+use ConfigDescriptor;
+use ConfigId;
+use select_config;
 use ContextError;
+use ContextInfo;
 use CreationError;
+use Extensions;
 use GlAttributes;
+use GlProfile;
 use GlRequest;
 use PixelFormat;
 use PixelFormatRequirements;
@@ -12,10 +19,14 @@ use ReleaseBehavior;
 use Robustness;
 use Api;
 
+use api::gl;
+
 use std::ffi::{CStr, CString};
 use std::os::raw::{c_void, c_int};
 use std::{mem, ptr};
-use std::cell::Cell;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicPtr, Ordering};
 
 pub mod ffi;
 
@@ -33,16 +44,84 @@ pub enum NativeDisplay {
     Device(ffi::EGLNativeDisplayType),
     /// Don't specify any display type. Useful on windows. `None` means `EGL_DEFAULT_DISPLAY`.
     Other(Option<ffi::EGLNativeDisplayType>),
+    /// No native display at all, see `EGL_MESA_platform_surfaceless`. Only offscreen rendering
+    /// is possible.
+    Surfaceless,
+}
+
+/// Specifies the kind of surface the context is going to render to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SurfaceType {
+    /// A native window, see `ContextPrototype::finish`.
+    Window,
+    /// An offscreen pbuffer, see `ContextPrototype::finish_pbuffer`.
+    PBuffer,
+    /// No surface at all, see `ContextPrototype::finish_surfaceless`.
+    Surfaceless,
 }
 
 pub struct Context {
     egl: ffi::egl::Egl,
     display: ffi::egl::types::EGLDisplay,
     context: ffi::egl::types::EGLContext,
-    surface: Cell<ffi::egl::types::EGLSurface>,
+    // not a `Cell`, as the surface can be replaced from any thread
+    surface: AtomicPtr<c_void>,
     api: Api,
     pixel_format: PixelFormat,
+    info: ContextInfo,
+    extensions: Extensions,
     config_id: ffi::egl::types::EGLConfig,
+    egl_version: (ffi::egl::types::EGLint, ffi::egl::types::EGLint),
+    // the requested version, `None` for the latest one
+    version: Option<(u8, u8)>,
+    // shared with the contexts recreated from this one
+    display_guard: Arc<DisplayGuard>,
+}
+
+lazy_static! {
+    // the number of guards of each display, by address
+    static ref DISPLAY_GUARDS: Mutex<HashMap<usize, usize>> = Mutex::new(HashMap::new());
+}
+
+/// Terminates the display when the last guard of the display is dropped.
+///
+/// `eglTerminate` destroys every context of the display, and `eglGetDisplay` returns the same
+/// display for the same native display. Every context holds a guard, and so does
+/// `available_configs` while it queries the display, so that the display is only terminated
+/// once nothing uses it anymore.
+struct DisplayGuard {
+    egl: ffi::egl::Egl,
+    display: ffi::egl::types::EGLDisplay,
+}
+
+impl DisplayGuard {
+    /// Must be called before initializing the display, so that the display can't be
+    /// terminated in between by the last guard of another thread.
+    fn new(egl: &ffi::egl::Egl, display: ffi::egl::types::EGLDisplay) -> DisplayGuard {
+        *DISPLAY_GUARDS.lock().unwrap().entry(display as usize).or_insert(0) += 1;
+        DisplayGuard {
+            egl: egl.clone(),
+            display: display,
+        }
+    }
+}
+
+impl Drop for DisplayGuard {
+    fn drop(&mut self) {
+        let mut guards = DISPLAY_GUARDS.lock().unwrap();
+        let last = {
+            let count = guards.get_mut(&(self.display as usize)).unwrap();
+            *count -= 1;
+            *count == 0
+        };
+        if last {
+            guards.remove(&(self.display as usize));
+            // still locked, so that the display isn't initialized again in the meantime
+            unsafe {
+                self.egl.Terminate(self.display);
+            }
+        }
+    }
 }
 
 #[cfg(target_os = "android")]
@@ -56,19 +135,7 @@ fn get_native_display(egl: &ffi::egl::Egl,
 fn get_native_display(egl: &ffi::egl::Egl,
                       native_display: NativeDisplay) -> *const c_void {
     // the first step is to query the list of extensions without any display, if supported
-    let dp_extensions = unsafe {
-        let p = egl.QueryString(ffi::egl::NO_DISPLAY, ffi::egl::EXTENSIONS as i32);
-
-        // this possibility is available only with EGL 1.5 or EGL_EXT_platform_base, otherwise
-        // `eglQueryString` returns an error
-        if p.is_null() {
-            vec![]
-        } else {
-            let p = CStr::from_ptr(p);
-            let list = String::from_utf8(p.to_bytes().to_vec()).unwrap_or_else(|_| format!(""));
-            list.split(' ').map(|e| e.to_string()).collect::<Vec<_>>()
-        }
-    };
+    let dp_extensions = unsafe { client_extensions(egl) };
 
     let has_dp_extension = |e: &str| dp_extensions.iter().find(|s| s == &e).is_some();
 
@@ -143,6 +210,23 @@ fn get_native_display(egl: &ffi::egl::Egl,
                                             ptr::null()) }
         },
 
+        NativeDisplay::Surfaceless if has_dp_extension("EGL_MESA_platform_surfaceless") &&
+                                      egl.GetPlatformDisplay.is_loaded() =>
+        {
+            unsafe { egl.GetPlatformDisplay(ffi::egl::PLATFORM_SURFACELESS_MESA,
+                                            ffi::egl::DEFAULT_DISPLAY as *mut _, ptr::null()) }
+        },
+
+        NativeDisplay::Surfaceless if has_dp_extension("EGL_MESA_platform_surfaceless") &&
+                                      egl.GetPlatformDisplayEXT.is_loaded() =>
+        {
+            unsafe { egl.GetPlatformDisplayEXT(ffi::egl::PLATFORM_SURFACELESS_MESA,
+                                               ffi::egl::DEFAULT_DISPLAY as *mut _, ptr::null()) }
+        },
+
+        // falling back to the default display would silently require a windowing system
+        NativeDisplay::Surfaceless => ptr::null(),
+
         NativeDisplay::X11(Some(display)) | NativeDisplay::Gbm(Some(display)) |
         NativeDisplay::Wayland(Some(display)) | NativeDisplay::Device(display) |
         NativeDisplay::Other(Some(display)) => {
@@ -161,17 +245,17 @@ impl Context {
     ///
     /// This function initializes some things and chooses the pixel format.
     ///
-    /// To finish the process, you must call `.finish(window)` on the `ContextPrototype`.
+    /// To finish the process, you must call `.finish(window)`, `.finish_pbuffer(dimensions)` or
+    /// `.finish_surfaceless()` on the `ContextPrototype`, depending on `surface_type`.
     pub fn new<'a>(
         egl: ffi::egl::Egl,
         pf_reqs: &PixelFormatRequirements,
         opengl: &'a GlAttributes<&'a Context>,
         native_display: NativeDisplay,
+        surface_type: SurfaceType,
     ) -> Result<ContextPrototype<'a>, CreationError>
     {
-        if opengl.sharing.is_some() {
-            unimplemented!()
-        }
+        let config_backend = config_backend(&native_display);
 
         // calling `eglGetDisplay` or equivalent
         let display = get_native_display(&egl, native_display);
@@ -179,6 +263,15 @@ impl Context {
         if display.is_null() {
             return Err(CreationError::OsError("Could not create EGL display object".to_string()));
         }
+        let display_guard = Arc::new(DisplayGuard::new(&egl, display));
+
+        // contexts can only share their objects with contexts of the same display
+        if let Some(share) = opengl.sharing {
+            if share.display != display {
+                let msg = "Cannot share an EGL context with a context of another EGLDisplay";
+                return Err(CreationError::PlatformSpecific(msg.into()));
+            }
+        }
 
         let egl_version = unsafe {
             let mut major: ffi::egl::types::EGLint = mem::uninitialized();
@@ -193,14 +286,7 @@ impl Context {
 
         // the list of extensions supported by the client once initialized is different from the
         // list of extensions obtained earlier
-        let extensions = if egl_version >= (1, 2) {
-            let p = unsafe { CStr::from_ptr(egl.QueryString(display, ffi::egl::EXTENSIONS as i32)) };
-            let list = String::from_utf8(p.to_bytes().to_vec()).unwrap_or_else(|_| format!(""));
-            list.split(' ').map(|e| e.to_string()).collect::<Vec<_>>()
-
-        } else {
-            vec![]
-        };
+        let extensions = unsafe { display_extensions(&egl, display, egl_version) };
 
         // binding the right API and choosing the version
         let (version, api) = unsafe {
@@ -253,9 +339,13 @@ impl Context {
         };
 
         let (config_id, pixel_format) = unsafe {
-            try!(choose_fbconfig(&egl, display, &egl_version, api, version, pf_reqs))
+            try!(choose_fbconfig(&egl, display, &egl_version, &extensions, api, version, pf_reqs,
+                                 surface_type, config_backend))
         };
 
+        let srgb = pf_reqs.srgb && (egl_version >= (1, 5) ||
+                                    extensions.iter().any(|s| s == "EGL_KHR_gl_colorspace"));
+
         Ok(ContextPrototype {
             opengl: opengl,
             egl: egl,
@@ -266,18 +356,71 @@ impl Context {
             version: version,
             config_id: config_id,
             pixel_format: pixel_format,
+            srgb: srgb,
+            release_behavior: pf_reqs.release_behavior,
+            display_guard: display_guard,
         })
     }
 
+    /// Creates a new context with the same config, API and version that renders to the surface
+    /// of this context, for example to replace a context that was lost. `opengl.sharing` is
+    /// usually `None`, as the objects of a lost context are lost as well.
+    ///
+    /// The surface is moved to the new context: afterwards, `swap_buffers` returns
+    /// `ContextError::ContextLost` on this context.
+    pub fn recreate<'a>(&self, opengl: &'a GlAttributes<&'a Context>)
+                        -> Result<Context, CreationError>
+    {
+        // the API is bound per thread, and may not be the one of this context
+        if self.egl_version >= (1, 2) {
+            let api = match self.api {
+                Api::OpenGlEs => ffi::egl::OPENGL_ES_API,
+                _ => ffi::egl::OPENGL_API,
+            };
+            if unsafe { self.egl.BindAPI(api) } == 0 {
+                return Err(CreationError::OpenGlVersionNotSupported);
+            }
+        }
+
+        let prototype = ContextPrototype {
+            opengl: opengl,
+            egl: self.egl.clone(),
+            display: self.display,
+            egl_version: self.egl_version,
+            extensions: unsafe { display_extensions(&self.egl, self.display, self.egl_version) },
+            api: self.api,
+            version: self.version,
+            config_id: self.config_id,
+            pixel_format: self.pixel_format.clone(),
+            srgb: self.pixel_format.srgb,
+            release_behavior: self.pixel_format.release_behavior,
+            display_guard: self.display_guard.clone(),
+        };
+
+        let context = prototype.finish_impl(self.surface())?;
+        self.replace_surface(ffi::egl::NO_SURFACE);
+        Ok(context)
+    }
+
+    #[inline]
+    fn surface(&self) -> ffi::egl::types::EGLSurface {
+        self.surface.load(Ordering::SeqCst) as *const _
+    }
+
+    /// Replaces the surface of the context, and returns the previous one.
+    #[inline]
+    fn replace_surface(&self, surface: ffi::egl::types::EGLSurface)
+                       -> ffi::egl::types::EGLSurface
+    {
+        self.surface.swap(surface as *mut _, Ordering::SeqCst) as *const _
+    }
+
     pub unsafe fn make_current(&self) -> Result<(), ContextError> {
-        let ret = self.egl.MakeCurrent(self.display, self.surface.get(), self.surface.get(), self.context);
+        let surface = self.surface();
+        let ret = self.egl.MakeCurrent(self.display, surface, surface, self.context);
 
         if ret == 0 {
-            match self.egl.GetError() as u32 {
-                ffi::egl::CONTEXT_LOST => return Err(ContextError::ContextLost),
-                err => panic!("eglMakeCurrent failed (eglGetError returned 0x{:x})", err)
-            }
-
+            Err(context_error(&self.egl, "eglMakeCurrent"))
         } else {
             Ok(())
         }
@@ -298,20 +441,17 @@ impl Context {
 
     #[inline]
     pub fn swap_buffers(&self) -> Result<(), ContextError> {
-        if self.surface.get() == ffi::egl::NO_SURFACE {
+        let surface = self.surface();
+        if surface == ffi::egl::NO_SURFACE {
             return Err(ContextError::ContextLost);
         }
 
         let ret = unsafe {
-            self.egl.SwapBuffers(self.display, self.surface.get())
+            self.egl.SwapBuffers(self.display, surface)
         };
 
         if ret == 0 {
-            match unsafe { self.egl.GetError() } as u32 {
-                ffi::egl::CONTEXT_LOST => return Err(ContextError::ContextLost),
-                err => panic!("eglSwapBuffers failed (eglGetError returned 0x{:x})", err)
-            }
-
+            Err(unsafe { context_error(&self.egl, "eglSwapBuffers") })
         } else {
             Ok(())
         }
@@ -327,26 +467,71 @@ impl Context {
         self.pixel_format.clone()
     }
 
+    #[inline]
+    pub fn get_context_info(&self) -> ContextInfo {
+        self.info.clone()
+    }
+
+    /// Returns the extensions of the display, followed by the client extensions.
+    #[inline]
+    pub fn get_extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
     #[inline]
     pub unsafe fn raw_handle(&self) -> ffi::egl::types::EGLContext {
         self.context
     }
 
+    /// Replaces the pbuffer of the context with a new one of the given dimensions, using the
+    /// same config. If the context is current, the new pbuffer is made current.
+    ///
+    /// The content of the old pbuffer is lost. On error, for example if the dimensions are
+    /// above `EGL_MAX_PBUFFER_WIDTH` or `EGL_MAX_PBUFFER_HEIGHT`, the old pbuffer is kept.
+    pub unsafe fn resize_pbuffer(&self, dimensions: (u32, u32)) -> Result<(), CreationError> {
+        let surface = create_pbuffer_surface(&self.egl, self.display, self.config_id, dimensions,
+                                             self.pixel_format.srgb);
+        if surface.is_null() {
+            return Err(CreationError::OsError(format!("eglCreatePbufferSurface failed: 0x{:x}",
+                                                      self.egl.GetError())));
+        }
+
+        if self.is_current() &&
+           self.egl.MakeCurrent(self.display, surface, surface, self.context) == 0
+        {
+            // a lost context is reported by the next `make_current` or `swap_buffers`
+            let error = self.egl.GetError() as u32;
+            if error != ffi::egl::CONTEXT_LOST {
+                self.egl.DestroySurface(self.display, surface);
+                return Err(CreationError::OsError(format!("eglMakeCurrent failed: 0x{:x}",
+                                                          error)));
+            }
+        }
+
+        let old_surface = self.replace_surface(surface);
+        if old_surface != ffi::egl::NO_SURFACE {
+            self.egl.DestroySurface(self.display, old_surface);
+        }
+        Ok(())
+    }
+
     // Handle Android Life Cycle.
     // Android has started the activity or sent it to foreground.
     // Create a new surface and attach it to the recreated ANativeWindow.
     // Restore the EGLContext.
     #[cfg(target_os = "android")]
     pub unsafe fn on_surface_created(&self, native_window: ffi::EGLNativeWindowType) {
-        if (self.surface.get() != ffi::egl::NO_SURFACE) {
+        if (self.surface() != ffi::egl::NO_SURFACE) {
             return;
         }
-        self.surface.set(self.egl.CreateWindowSurface(self.display, self.config_id, native_window, ptr::null()));
-        if self.surface.get().is_null() {
+        let attributes = colorspace_attributes(self.pixel_format.srgb);
+        self.replace_surface(self.egl.CreateWindowSurface(self.display, self.config_id, native_window, attributes.as_ptr()));
+        if self.surface().is_null() {
             panic!("on_surface_created: eglCreateWindowSurface failed")
         }
-        let ret = self.egl.MakeCurrent(self.display, self.surface.get(), self.surface.get(), self.context);
-        if ret == 0 {
+        let ret = self.egl.MakeCurrent(self.display, self.surface(), self.surface(), self.context);
+        // a lost context is reported by the next `make_current` or `swap_buffers`
+        if ret == 0 && self.egl.GetError() as u32 != ffi::egl::CONTEXT_LOST {
             panic!("on_surface_created: eglMakeCurrent failed");
         }
     }
@@ -357,7 +542,7 @@ impl Context {
     // The EGLContext is not destroyed so it can be restored later.
     #[cfg(target_os = "android")]
     pub unsafe fn on_surface_destroyed(&self) {
-        if (self.surface.get() == ffi::egl::NO_SURFACE) {
+        if (self.surface() == ffi::egl::NO_SURFACE) {
             return;
         }
         let ret = self.egl.MakeCurrent(self.display, ffi::egl::NO_SURFACE, ffi::egl::NO_SURFACE, ffi::egl::NO_CONTEXT);
@@ -365,8 +550,8 @@ impl Context {
             panic!("on_surface_destroyed: eglMakeCurrent failed");
         }
 
-        self.egl.DestroySurface(self.display, self.surface.get());
-        self.surface.set(ffi::egl::NO_SURFACE);
+        let surface = self.replace_surface(ffi::egl::NO_SURFACE);
+        self.egl.DestroySurface(self.display, surface);
     }
 }
 
@@ -379,8 +564,10 @@ impl Drop for Context {
             // we don't call MakeCurrent(0, 0) because we are not sure that the context
             // is still the current one
             self.egl.DestroyContext(self.display, self.context);
-            self.egl.DestroySurface(self.display, self.surface.get());
-            self.egl.Terminate(self.display);
+            if self.surface() != ffi::egl::NO_SURFACE {
+                self.egl.DestroySurface(self.display, self.surface());
+            }
+            // the display is terminated when `display_guard` is dropped
         }
     }
 }
@@ -395,6 +582,10 @@ pub struct ContextPrototype<'a> {
     version: Option<(u8, u8)>,
     config_id: ffi::egl::types::EGLConfig,
     pixel_format: PixelFormat,
+    // true if the surface should use the sRGB colorspace, and EGL can do it
+    srgb: bool,
+    release_behavior: ReleaseBehavior,
+    display_guard: Arc<DisplayGuard>,
 }
 
 impl<'a> ContextPrototype<'a> {
@@ -407,106 +598,218 @@ impl<'a> ContextPrototype<'a> {
         value
     }
 
-    pub fn finish(self, native_window: ffi::EGLNativeWindowType)
+    pub fn finish(mut self, native_window: ffi::EGLNativeWindowType)
                   -> Result<Context, CreationError>
     {
-        let surface = unsafe {
-            let surface = self.egl.CreateWindowSurface(self.display, self.config_id, native_window,
-                                                       ptr::null());
+        let (surface, srgb) = unsafe {
+            let (surface, srgb) = with_srgb_fallback(self.srgb, |srgb| {
+                let attributes = colorspace_attributes(srgb);
+                self.egl.CreateWindowSurface(self.display, self.config_id, native_window,
+                                             attributes.as_ptr())
+            });
             if surface.is_null() {
                 return Err(CreationError::OsError(format!("eglCreateWindowSurface failed")))
             }
-            surface
+            (surface, srgb)
         };
 
+        self.pixel_format.srgb = srgb;
         self.finish_impl(surface)
     }
 
-    pub fn finish_pbuffer(self, dimensions: (u32, u32)) -> Result<Context, CreationError> {
-        let attrs = &[
-            ffi::egl::WIDTH as c_int, dimensions.0 as c_int,
-            ffi::egl::HEIGHT as c_int, dimensions.1 as c_int,
-            ffi::egl::NONE as c_int,
-        ];
-
-        let surface = unsafe {
-            let surface = self.egl.CreatePbufferSurface(self.display, self.config_id,
-                                                        attrs.as_ptr());
+    pub fn finish_pbuffer(mut self, dimensions: (u32, u32)) -> Result<Context, CreationError> {
+        let (surface, srgb) = unsafe {
+            let (surface, srgb) = with_srgb_fallback(self.srgb, |srgb| {
+                create_pbuffer_surface(&self.egl, self.display, self.config_id, dimensions, srgb)
+            });
             if surface.is_null() {
                 return Err(CreationError::OsError(format!("eglCreatePbufferSurface failed")))
             }
-            surface
+            (surface, srgb)
         };
 
+        self.pixel_format.srgb = srgb;
         self.finish_impl(surface)
     }
 
-    fn finish_impl(self, surface: ffi::egl::types::EGLSurface)
+    /// Finishes the context without creating any surface.
+    ///
+    /// The context can only render to framebuffer objects. Requires
+    /// `EGL_KHR_surfaceless_context`.
+    pub fn finish_surfaceless(self) -> Result<Context, CreationError> {
+        if self.extensions.iter().find(|s| s == &"EGL_KHR_surfaceless_context").is_none() {
+            return Err(CreationError::NotSupported);
+        }
+
+        self.finish_impl(ffi::egl::NO_SURFACE)
+    }
+
+    fn finish_impl(mut self, surface: ffi::egl::types::EGLSurface)
                    -> Result<Context, CreationError>
     {
-        let context = unsafe {
-            if let Some(version) = self.version {
-                try!(create_context(&self.egl, self.display, &self.egl_version,
-                                    &self.extensions, self.api, version, self.config_id,
-                                    self.opengl.debug, self.opengl.robustness))
-
-            } else if self.api == Api::OpenGlEs {
-                if let Ok(ctxt) = create_context(&self.egl, self.display, &self.egl_version,
-                                                 &self.extensions, self.api, (2, 0), self.config_id,
-                                                 self.opengl.debug, self.opengl.robustness)
-                {
-                    ctxt
-                } else if let Ok(ctxt) = create_context(&self.egl, self.display, &self.egl_version,
-                                                        &self.extensions, self.api, (1, 0),
-                                                        self.config_id, self.opengl.debug,
-                                                        self.opengl.robustness)
-                {
-                    ctxt
-                } else {
-                    return Err(CreationError::OpenGlVersionNotSupported);
-                }
+        let share = match self.opengl.sharing {
+            Some(ctxt) => ctxt.context,
+            None => ptr::null(),
+        };
 
-            } else {
-                if let Ok(ctxt) = create_context(&self.egl, self.display, &self.egl_version,
-                                                 &self.extensions, self.api, (3, 2), self.config_id,
-                                                 self.opengl.debug, self.opengl.robustness)
-                {
-                    ctxt
-                } else if let Ok(ctxt) = create_context(&self.egl, self.display, &self.egl_version,
-                                                        &self.extensions, self.api, (3, 1),
-                                                        self.config_id, self.opengl.debug,
-                                                        self.opengl.robustness)
-                {
-                    ctxt
-                } else if let Ok(ctxt) = create_context(&self.egl, self.display, &self.egl_version,
-                                                        &self.extensions, self.api, (1, 0),
-                                                        self.config_id, self.opengl.debug,
-                                                        self.opengl.robustness)
-                {
-                    ctxt
-                } else {
-                    return Err(CreationError::OpenGlVersionNotSupported);
-                }
+        let (context, mut info) = {
+            let create = |version| unsafe {
+                create_context(&self.egl, self.display, &self.egl_version, &self.extensions,
+                               self.api, version, self.config_id, self.opengl,
+                               self.release_behavior, share)
+            };
+
+            match (self.version, self.api) {
+                (Some(version), _) => try!(create(version)),
+                (None, api) => {
+                    // try all the versions in descending order because some non-compliant drivers
+                    // don't return the latest supported version but the one requested
+                    let versions: &[(u8, u8)] = match api {
+                        Api::OpenGlEs => &[(2, 0), (1, 0)],
+                        _ => &[(4, 6), (4, 5), (4, 4), (4, 3), (4, 2), (4, 1), (4, 0), (3, 3),
+                               (3, 2), (3, 1), (1, 0)],
+                    };
+
+                    match versions.iter().filter_map(|&version| create(version).ok()).next() {
+                        Some(ctxt) => ctxt,
+                        None => return Err(CreationError::OpenGlVersionNotSupported),
+                    }
+                },
             }
         };
 
+        // drivers may provide a later version than the one requested
+        if let Some(version) = unsafe { query_version(&self.egl, self.display, surface, context) } {
+            info.version = version;
+        }
+
+        if self.release_behavior == ReleaseBehavior::None &&
+           supports_flush_control(&self.extensions)
+        {
+            self.pixel_format.release_behavior = ReleaseBehavior::None;
+        }
+
+        let extensions = unsafe {
+            let client_extensions = client_extensions(&self.egl);
+            Extensions::new(self.extensions.iter().cloned().chain(client_extensions))
+        };
+
         Ok(Context {
             egl: self.egl,
             display: self.display,
             context: context,
-            surface: Cell::new(surface),
+            surface: AtomicPtr::new(surface as *mut _),
             api: self.api,
             pixel_format: self.pixel_format,
-            config_id: self.config_id
+            info: info,
+            extensions: extensions,
+            config_id: self.config_id,
+            egl_version: self.egl_version,
+            version: self.version,
+            display_guard: self.display_guard,
         })
     }
 }
 
+/// Makes `context` current to read its `GL_VERSION`, then makes the previously current context
+/// of the thread current again. Returns `None` if `context` couldn't be made current.
+unsafe fn query_version(egl: &ffi::egl::Egl, display: ffi::egl::types::EGLDisplay,
+                        surface: ffi::egl::types::EGLSurface,
+                        context: ffi::egl::types::EGLContext) -> Option<(u8, u8)>
+{
+    let previous_display = egl.GetCurrentDisplay();
+    let previous_context = egl.GetCurrentContext();
+    let previous_draw = egl.GetCurrentSurface(ffi::egl::DRAW as i32);
+    let previous_read = egl.GetCurrentSurface(ffi::egl::READ as i32);
+
+    if egl.MakeCurrent(display, surface, surface, context) == 0 {
+        return None;
+    }
+
+    let gl = gl::Gl::load_with(|symbol| {
+        let symbol = CString::new(symbol).unwrap();
+        egl.GetProcAddress(symbol.as_ptr()) as *const _
+    });
+    let (_, version) = gl::get_version(&gl);
+
+    if previous_context == ffi::egl::NO_CONTEXT {
+        egl.MakeCurrent(display, ffi::egl::NO_SURFACE, ffi::egl::NO_SURFACE,
+                        ffi::egl::NO_CONTEXT);
+    } else {
+        egl.MakeCurrent(previous_display, previous_draw, previous_read, previous_context);
+    }
+
+    Some(version)
+}
+
+/// Returns the error of a failed `eglMakeCurrent` or `eglSwapBuffers` call.
+///
+/// `EGL_CONTEXT_LOST` is returned after a power management event or a graphics reset, and the
+/// context must then be recreated. The surface may be lost as well during a reset, in which
+/// case `EGL_BAD_SURFACE` or `EGL_BAD_NATIVE_WINDOW` is returned as a `ContextError::OsError`.
+unsafe fn context_error(egl: &ffi::egl::Egl, function: &str) -> ContextError {
+    match egl.GetError() as u32 {
+        ffi::egl::CONTEXT_LOST => ContextError::ContextLost,
+        err => ContextError::OsError(format!("{} failed (eglGetError returned 0x{:x})",
+                                             function, err)),
+    }
+}
+
+unsafe fn create_pbuffer_surface(egl: &ffi::egl::Egl, display: ffi::egl::types::EGLDisplay,
+                                 config_id: ffi::egl::types::EGLConfig, dimensions: (u32, u32),
+                                 srgb: bool)
+                                 -> ffi::egl::types::EGLSurface
+{
+    let mut attrs = vec![
+        ffi::egl::WIDTH as c_int, dimensions.0 as c_int,
+        ffi::egl::HEIGHT as c_int, dimensions.1 as c_int,
+    ];
+    attrs.extend(colorspace_attributes(srgb));
+
+    egl.CreatePbufferSurface(display, config_id, attrs.as_ptr())
+}
+
+/// Returns the `NONE`-terminated surface attributes that select the colorspace.
+///
+/// `EGL_GL_COLORSPACE` has the same value as `EGL_GL_COLORSPACE_KHR` of
+/// `EGL_KHR_gl_colorspace`.
+fn colorspace_attributes(srgb: bool) -> Vec<c_int> {
+    if srgb {
+        vec![ffi::egl::GL_COLORSPACE as c_int, ffi::egl::GL_COLORSPACE_SRGB as c_int,
+             ffi::egl::NONE as c_int]
+    } else {
+        vec![ffi::egl::NONE as c_int]
+    }
+}
+
+/// Calls `create` with `true` if `srgb` is true, then with `false` if that failed. Returns the
+/// surface and whether it is sRGB.
+///
+/// Drivers may not support sRGB surfaces for every config, even with `EGL_KHR_gl_colorspace`.
+unsafe fn with_srgb_fallback<F>(srgb: bool, create: F) -> (ffi::egl::types::EGLSurface, bool)
+    where F: Fn(bool) -> ffi::egl::types::EGLSurface
+{
+    if srgb {
+        let surface = create(true);
+        if !surface.is_null() {
+            return (surface, true);
+        }
+    }
+
+    (create(false), false)
+}
+
 unsafe fn choose_fbconfig(egl: &ffi::egl::Egl, display: ffi::egl::types::EGLDisplay,
                           egl_version: &(ffi::egl::types::EGLint, ffi::egl::types::EGLint),
-                          api: Api, version: Option<(u8, u8)>, reqs: &PixelFormatRequirements)
+                          extensions: &[String], api: Api, version: Option<(u8, u8)>,
+                          reqs: &PixelFormatRequirements, surface_type: SurfaceType,
+                          config_backend: ConfigBackend)
                           -> Result<(ffi::egl::types::EGLConfig, PixelFormat), CreationError>
 {
+    if let Some(id) = reqs.config_id {
+        return choose_config_by_id(egl, display, extensions, id, surface_type, config_backend);
+    }
+
     let descriptor = {
         let mut out: Vec<c_int> = Vec::with_capacity(37);
 
@@ -518,7 +821,12 @@ unsafe fn choose_fbconfig(egl: &ffi::egl::Egl, display: ffi::egl::types::EGLDisp
         out.push(ffi::egl::SURFACE_TYPE as c_int);
         // TODO: Some versions of Mesa report a BAD_ATTRIBUTE error
         // if we ask for PBUFFER_BIT as well as WINDOW_BIT
-        out.push((ffi::egl::WINDOW_BIT) as c_int);
+        out.push(match surface_type {
+            SurfaceType::Window => ffi::egl::WINDOW_BIT as c_int,
+            SurfaceType::PBuffer => ffi::egl::PBUFFER_BIT as c_int,
+            // the default value is `WINDOW_BIT`, so we need to explicitly accept any config
+            SurfaceType::Surfaceless => 0,
+        });
 
         match (api, version) {
             (Api::OpenGlEs, Some((3, _))) => {
@@ -563,13 +871,13 @@ unsafe fn choose_fbconfig(egl: &ffi::egl::Egl, display: ffi::egl::types::EGLDisp
             });
         }
 
-        if let Some(color) = reqs.color_bits {
+        if let Some((red, green, blue)) = reqs.rgb_bits() {
             out.push(ffi::egl::RED_SIZE as c_int);
-            out.push((color / 3) as c_int);
+            out.push(red as c_int);
             out.push(ffi::egl::GREEN_SIZE as c_int);
-            out.push((color / 3 + if color % 3 != 0 { 1 } else { 0 }) as c_int);
+            out.push(green as c_int);
             out.push(ffi::egl::BLUE_SIZE as c_int);
-            out.push((color / 3 + if color % 3 == 2 { 1 } else { 0 }) as c_int);
+            out.push(blue as c_int);
         }
 
         if let Some(alpha) = reqs.alpha_bits {
@@ -600,21 +908,65 @@ unsafe fn choose_fbconfig(egl: &ffi::egl::Egl, display: ffi::egl::types::EGLDisp
             return Err(CreationError::NoAvailablePixelFormat);
         }
 
-        // FIXME: srgb is not taken into account
-
-        match reqs.release_behavior {
-            ReleaseBehavior::Flush => (),
-            ReleaseBehavior::None => {
-                // TODO: with EGL you need to manually set the behavior
-                unimplemented!()
-            },
+        if extensions.iter().any(|s| s == "EGL_EXT_pixel_format_float") {
+            out.push(ffi::egl::COLOR_COMPONENT_TYPE_EXT as c_int);
+            out.push(if reqs.float_color_buffer {
+                ffi::egl::COLOR_COMPONENT_TYPE_FLOAT_EXT as c_int
+            } else {
+                ffi::egl::COLOR_COMPONENT_TYPE_FIXED_EXT as c_int
+            });
+        } else if reqs.float_color_buffer {
+            return Err(CreationError::NoAvailablePixelFormat);
         }
 
+        // sRGB is a property of the surfaces, see `ContextPrototype::srgb`
+
+        // the release behavior is an attribute of the context, see `create_context`
+
         out.push(ffi::egl::NONE as c_int);
         out
     };
 
-    // calling `eglChooseConfig`
+    // calling `eglChooseConfig` to get every matching config, the first one isn't necessarily
+    // the closest to the requirements
+    let mut num_configs = 0;
+    if egl.ChooseConfig(display, descriptor.as_ptr(), ptr::null_mut(), 0, &mut num_configs) == 0 {
+        return Err(CreationError::OsError(format!("eglChooseConfig failed")));
+    }
+    let mut configs = vec![ptr::null(); num_configs as usize];
+    if egl.ChooseConfig(display, descriptor.as_ptr(), configs.as_mut_ptr(), num_configs,
+                        &mut num_configs) == 0
+    {
+        return Err(CreationError::OsError(format!("eglChooseConfig failed")));
+    }
+    configs.truncate(num_configs as usize);
+
+    let mut candidates = Vec::with_capacity(configs.len());
+    for &config in &configs {
+        candidates.push(try!(describe_config(egl, display, extensions, config, config_backend)));
+    }
+
+    // sRGB and double buffering are properties of the surfaces, which the descriptors of the
+    // configs can't report
+    let reqs = PixelFormatRequirements { srgb: false, double_buffer: None, .. reqs.clone() };
+    match select_config(&candidates, &reqs) {
+        Some(index) => Ok((configs[index], candidates[index].pixel_format.clone())),
+        None => Err(CreationError::NoAvailablePixelFormat),
+    }
+}
+
+/// Returns the config whose `EGL_CONFIG_ID` is `id`, if it supports `surface_type`.
+unsafe fn choose_config_by_id(egl: &ffi::egl::Egl, display: ffi::egl::types::EGLDisplay,
+                              extensions: &[String], id: ConfigId, surface_type: SurfaceType,
+                              config_backend: ConfigBackend)
+                              -> Result<(ffi::egl::types::EGLConfig, PixelFormat), CreationError>
+{
+    if id.backend != config_backend {
+        return Err(CreationError::NoAvailablePixelFormat);
+    }
+
+    // the other attributes are ignored when `EGL_CONFIG_ID` is given
+    let descriptor = [ffi::egl::CONFIG_ID as c_int, id.id, ffi::egl::NONE as c_int];
     let mut config_id = mem::uninitialized();
     let mut num_configs = mem::uninitialized();
     if egl.ChooseConfig(display, descriptor.as_ptr(), &mut config_id, 1, &mut num_configs) == 0 {
@@ -624,7 +976,26 @@ unsafe fn choose_fbconfig(egl: &ffi::egl::Egl, display: ffi::egl::types::EGLDisp
         return Err(CreationError::NoAvailablePixelFormat);
     }
 
-    // analyzing each config
+    let mut surface_bits = 0;
+    egl.GetConfigAttrib(display, config_id, ffi::egl::SURFACE_TYPE as c_int, &mut surface_bits);
+    let required_bits = match surface_type {
+        SurfaceType::Window => ffi::egl::WINDOW_BIT as c_int,
+        SurfaceType::PBuffer => ffi::egl::PBUFFER_BIT as c_int,
+        SurfaceType::Surfaceless => 0,
+    };
+    if surface_bits & required_bits != required_bits {
+        return Err(CreationError::NoAvailablePixelFormat);
+    }
+
+    let desc = try!(config_pixel_format(egl, display, extensions, config_id, config_backend));
+    Ok((config_id, desc))
+}
+
+unsafe fn config_pixel_format(egl: &ffi::egl::Egl, display: ffi::egl::types::EGLDisplay,
+                              extensions: &[String], config_id: ffi::egl::types::EGLConfig,
+                              config_backend: ConfigBackend)
+                              -> Result<PixelFormat, CreationError>
+{
     macro_rules! attrib {
         ($egl:expr, $display:expr, $config:expr, $attr:expr) => (
             {
@@ -639,37 +1010,187 @@ unsafe fn choose_fbconfig(egl: &ffi::egl::Egl, display: ffi::egl::types::EGLDisp
         )
     };
 
-    let desc = PixelFormat {
+    let red_bits = attrib!(egl, display, config_id, ffi::egl::RED_SIZE) as u8;
+    let green_bits = attrib!(egl, display, config_id, ffi::egl::GREEN_SIZE) as u8;
+    let blue_bits = attrib!(egl, display, config_id, ffi::egl::BLUE_SIZE) as u8;
+
+    Ok(PixelFormat {
         hardware_accelerated: attrib!(egl, display, config_id, ffi::egl::CONFIG_CAVEAT)
                                       != ffi::egl::SLOW_CONFIG as i32,
-        color_bits: attrib!(egl, display, config_id, ffi::egl::RED_SIZE) as u8 +
-                    attrib!(egl, display, config_id, ffi::egl::BLUE_SIZE) as u8 +
-                    attrib!(egl, display, config_id, ffi::egl::GREEN_SIZE) as u8,
+        color_bits: red_bits + green_bits + blue_bits,
+        red_bits: red_bits,
+        green_bits: green_bits,
+        blue_bits: blue_bits,
         alpha_bits: attrib!(egl, display, config_id, ffi::egl::ALPHA_SIZE) as u8,
         depth_bits: attrib!(egl, display, config_id, ffi::egl::DEPTH_SIZE) as u8,
         stencil_bits: attrib!(egl, display, config_id, ffi::egl::STENCIL_SIZE) as u8,
+        accum_bits: 0,
+        aux_buffers: 0,
         stereoscopy: false,
         double_buffer: true,
         multisampling: match attrib!(egl, display, config_id, ffi::egl::SAMPLES) {
             0 | 1 => None,
             a => Some(a as u16),
         },
-        srgb: false,        // TODO: use EGL_KHR_gl_colorspace to know that
-    };
+        // set once the surface is created
+        srgb: false,
+        float_color_buffer: extensions.iter().any(|s| s == "EGL_EXT_pixel_format_float") &&
+                            attrib!(egl, display, config_id, ffi::egl::COLOR_COMPONENT_TYPE_EXT)
+                                == ffi::egl::COLOR_COMPONENT_TYPE_FLOAT_EXT as i32,
+        transparent: false,
+        release_behavior: ReleaseBehavior::Flush,
+        native_visual_id: match attrib!(egl, display, config_id, ffi::egl::NATIVE_VISUAL_ID) {
+            0 => None,
+            id => Some(id as u32),
+        },
+        config_id: Some(ConfigId {
+            backend: config_backend,
+            id: attrib!(egl, display, config_id, ffi::egl::CONFIG_ID),
+        }),
+    })
+}
 
-    Ok((config_id, desc))
+/// Lists the RGB configs of `native_display`.
+///
+/// The display is initialized but not terminated, as contexts may be using it.
+pub fn available_configs(egl: &ffi::egl::Egl, native_display: NativeDisplay)
+                         -> Result<Vec<ConfigDescriptor>, CreationError>
+{
+    let config_backend = config_backend(&native_display);
+    let display = get_native_display(egl, native_display);
+    if display.is_null() {
+        return Err(CreationError::OsError("Could not create EGL display object".to_string()));
+    }
+    // terminates the display afterwards, unless a context uses it
+    let _guard = DisplayGuard::new(egl, display);
+
+    unsafe {
+        let (mut major, mut minor) = (0, 0);
+        if egl.Initialize(display, &mut major, &mut minor) == 0 {
+            return Err(CreationError::OsError(format!("eglInitialize failed")))
+        }
+
+        let mut num_configs = 0;
+        if egl.GetConfigs(display, ptr::null_mut(), 0, &mut num_configs) == 0 {
+            return Err(CreationError::OsError(format!("eglGetConfigs failed")));
+        }
+        let mut configs = vec![ptr::null(); num_configs as usize];
+        if egl.GetConfigs(display, configs.as_mut_ptr(), num_configs, &mut num_configs) == 0 {
+            return Err(CreationError::OsError(format!("eglGetConfigs failed")));
+        }
+        configs.truncate(num_configs as usize);
+
+        let extensions = display_extensions(egl, display, (major, minor));
+
+        let mut descriptors = Vec::with_capacity(configs.len());
+        for config in configs {
+            // luminance configs can't be described by a `PixelFormat`
+            if (major, minor) >= (1, 2) {
+                let mut buffer_type = 0;
+                egl.GetConfigAttrib(display, config, ffi::egl::COLOR_BUFFER_TYPE as c_int,
+                                    &mut buffer_type);
+                if buffer_type != ffi::egl::RGB_BUFFER as c_int {
+                    continue;
+                }
+            }
+
+            descriptors.push(try!(describe_config(egl, display, &extensions, config,
+                                                  config_backend)));
+        }
+
+        Ok(descriptors)
+    }
+}
+
+unsafe fn describe_config(egl: &ffi::egl::Egl, display: ffi::egl::types::EGLDisplay,
+                          extensions: &[String], config: ffi::egl::types::EGLConfig,
+                          config_backend: ConfigBackend)
+                          -> Result<ConfigDescriptor, CreationError>
+{
+    let mut id = 0;
+    let mut surface_bits = 0;
+    if egl.GetConfigAttrib(display, config, ffi::egl::CONFIG_ID as c_int, &mut id) == 0 ||
+       egl.GetConfigAttrib(display, config, ffi::egl::SURFACE_TYPE as c_int,
+                           &mut surface_bits) == 0
+    {
+        return Err(CreationError::OsError(format!("eglGetConfigAttrib failed")));
+    }
+
+    let pixel_format = try!(config_pixel_format(egl, display, extensions, config,
+                                                config_backend));
+    Ok(ConfigDescriptor {
+        id: ConfigId { backend: config_backend, id: id },
+        float_color_buffer: pixel_format.float_color_buffer,
+        window: surface_bits & ffi::egl::WINDOW_BIT as c_int != 0,
+        pbuffer: surface_bits & ffi::egl::PBUFFER_BIT as c_int != 0,
+        pixel_format: pixel_format,
+    })
+}
+
+/// Returns true if the release behavior of the contexts can be chosen.
+#[inline]
+fn supports_flush_control(extensions: &[String]) -> bool {
+    extensions.iter().any(|s| s == "EGL_KHR_context_flush_control")
+}
+
+/// Returns the client extensions, which don't depend on any display.
+unsafe fn client_extensions(egl: &ffi::egl::Egl) -> Vec<String> {
+    let p = egl.QueryString(ffi::egl::NO_DISPLAY, ffi::egl::EXTENSIONS as i32);
+
+    // this possibility is available only with EGL 1.5 or EGL_EXT_platform_base, otherwise
+    // `eglQueryString` returns an error
+    if p.is_null() {
+        vec![]
+    } else {
+        let p = CStr::from_ptr(p);
+        let list = String::from_utf8(p.to_bytes().to_vec()).unwrap_or_else(|_| format!(""));
+        list.split(' ').map(|e| e.to_string()).collect::<Vec<_>>()
+    }
+}
+
+/// Returns the extensions supported by `display`, which must be initialized.
+unsafe fn display_extensions(egl: &ffi::egl::Egl, display: ffi::egl::types::EGLDisplay,
+                             egl_version: (ffi::egl::types::EGLint, ffi::egl::types::EGLint))
+                             -> Vec<String>
+{
+    if egl_version < (1, 2) {
+        return vec![];
+    }
+
+    let p = CStr::from_ptr(egl.QueryString(display, ffi::egl::EXTENSIONS as i32));
+    let list = String::from_utf8(p.to_bytes().to_vec()).unwrap_or_else(|_| format!(""));
+    list.split(' ').map(|e| e.to_string()).collect::<Vec<_>>()
+}
+
+/// Returns the backend of the configs of `native_display`.
+#[inline]
+fn config_backend(native_display: &NativeDisplay) -> ConfigBackend {
+    match *native_display {
+        NativeDisplay::Surfaceless => ConfigBackend::EglSurfaceless,
+        _ => ConfigBackend::Egl,
+    }
 }
 
 unsafe fn create_context(egl: &ffi::egl::Egl, display: ffi::egl::types::EGLDisplay,
                          egl_version: &(ffi::egl::types::EGLint, ffi::egl::types::EGLint),
                          extensions: &[String], api: Api, version: (u8, u8),
-                         config_id: ffi::egl::types::EGLConfig, gl_debug: bool,
-                         gl_robustness: Robustness)
-                         -> Result<ffi::egl::types::EGLContext, CreationError>
+                         config_id: ffi::egl::types::EGLConfig,
+                         opengl: &GlAttributes<&Context>, release_behavior: ReleaseBehavior,
+                         share: ffi::egl::types::EGLContext)
+                         -> Result<(ffi::egl::types::EGLContext, ContextInfo), CreationError>
 {
     let mut context_attributes = Vec::with_capacity(10);
     let mut flags = 0;
 
+    // updated below with what is actually requested
+    let mut info = ContextInfo {
+        version: (1, 0),
+        profile: None,
+        debug: false,
+        forward_compatible: false,
+        robustness: Robustness::NotRobust,
+    };
+
     if egl_version >= &(1, 5) || extensions.iter().find(|s| s == &"EGL_KHR_create_context")
                                                   .is_some()
     {
@@ -677,6 +1198,7 @@ unsafe fn create_context(egl: &ffi::egl::Egl, display: ffi::egl::types::EGLDispl
         context_attributes.push(version.0 as i32);
         context_attributes.push(ffi::egl::CONTEXT_MINOR_VERSION as i32);
         context_attributes.push(version.1 as i32);
+        info.version = version;
 
         // handling robustness
         let supports_robustness = egl_version >= &(1, 5) ||
@@ -684,13 +1206,14 @@ unsafe fn create_context(egl: &ffi::egl::Egl, display: ffi::egl::types::EGLDispl
                                             .find(|s| s == &"EGL_EXT_create_context_robustness")
                                             .is_some();
 
-        match gl_robustness {
+        match opengl.robustness {
             Robustness::NotRobust => (),
 
             Robustness::NoError => {
                 if extensions.iter().find(|s| s == &"EGL_KHR_create_context_no_error").is_some() {
                     context_attributes.push(ffi::egl::CONTEXT_OPENGL_NO_ERROR_KHR as c_int);
                     context_attributes.push(1);
+                    info.robustness = Robustness::NoError;
                 }
             },
 
@@ -700,6 +1223,7 @@ unsafe fn create_context(egl: &ffi::egl::Egl, display: ffi::egl::types::EGLDispl
                                             as c_int);
                     context_attributes.push(ffi::egl::NO_RESET_NOTIFICATION as c_int);
                     flags = flags | ffi::egl::CONTEXT_OPENGL_ROBUST_ACCESS as c_int;
+                    info.robustness = Robustness::RobustNoResetNotification;
                 } else {
                     return Err(CreationError::RobustnessNotSupported);
                 }
@@ -711,6 +1235,7 @@ unsafe fn create_context(egl: &ffi::egl::Egl, display: ffi::egl::types::EGLDispl
                                             as c_int);
                     context_attributes.push(ffi::egl::NO_RESET_NOTIFICATION as c_int);
                     flags = flags | ffi::egl::CONTEXT_OPENGL_ROBUST_ACCESS as c_int;
+                    info.robustness = Robustness::RobustNoResetNotification;
                 }
             },
 
@@ -720,6 +1245,7 @@ unsafe fn create_context(egl: &ffi::egl::Egl, display: ffi::egl::types::EGLDispl
                                             as c_int);
                     context_attributes.push(ffi::egl::LOSE_CONTEXT_ON_RESET as c_int);
                     flags = flags | ffi::egl::CONTEXT_OPENGL_ROBUST_ACCESS as c_int;
+                    info.robustness = Robustness::RobustLoseContextOnReset;
                 } else {
                     return Err(CreationError::RobustnessNotSupported);
                 }
@@ -731,14 +1257,43 @@ unsafe fn create_context(egl: &ffi::egl::Egl, display: ffi::egl::types::EGLDispl
                                             as c_int);
                     context_attributes.push(ffi::egl::LOSE_CONTEXT_ON_RESET as c_int);
                     flags = flags | ffi::egl::CONTEXT_OPENGL_ROBUST_ACCESS as c_int;
+                    info.robustness = Robustness::RobustLoseContextOnReset;
                 }
             },
         }
 
-        if gl_debug {
+        // the profile and the forward-compatible flag only exist for desktop OpenGL
+        if api == Api::OpenGl {
+            if let Some(profile) = opengl.profile {
+                let bit = match profile {
+                    GlProfile::Compatibility => ffi::egl::CONTEXT_OPENGL_COMPATIBILITY_PROFILE_BIT,
+                    GlProfile::Core => ffi::egl::CONTEXT_OPENGL_CORE_PROFILE_BIT,
+                };
+                context_attributes.push(ffi::egl::CONTEXT_OPENGL_PROFILE_MASK as i32);
+                context_attributes.push(bit as i32);
+            }
+
+            // profiles only exist since OpenGL 3.2, and the core profile is the default one
+            if version >= (3, 2) {
+                info.profile = Some(opengl.profile.unwrap_or(GlProfile::Core));
+            }
+
+            if opengl.forward_compatible {
+                if egl_version >= &(1, 5) {
+                    context_attributes.push(ffi::egl::CONTEXT_OPENGL_FORWARD_COMPATIBLE as i32);
+                    context_attributes.push(ffi::egl::TRUE as i32);
+                } else {
+                    flags = flags | ffi::egl::CONTEXT_OPENGL_FORWARD_COMPATIBLE_BIT_KHR as i32;
+                }
+                info.forward_compatible = true;
+            }
+        }
+
+        if opengl.debug {
             if egl_version >= &(1, 5) {
                 context_attributes.push(ffi::egl::CONTEXT_OPENGL_DEBUG as i32);
                 context_attributes.push(ffi::egl::TRUE as i32);
+                info.debug = true;
             }
 
             // TODO: using this flag sometimes generates an error
@@ -753,7 +1308,7 @@ unsafe fn create_context(egl: &ffi::egl::Egl, display: ffi::egl::types::EGLDispl
 
     } else if egl_version >= &(1, 3) && api == Api::OpenGlEs {
         // robustness is not supported
-        match gl_robustness {
+        match opengl.robustness {
             Robustness::RobustNoResetNotification | Robustness::RobustLoseContextOnReset => {
                 return Err(CreationError::RobustnessNotSupported);
             },
@@ -762,12 +1317,17 @@ unsafe fn create_context(egl: &ffi::egl::Egl, display: ffi::egl::types::EGLDispl
 
         context_attributes.push(ffi::egl::CONTEXT_CLIENT_VERSION as i32);
         context_attributes.push(version.0 as i32);
+        info.version = (version.0, 0);
+    }
+
+    if release_behavior == ReleaseBehavior::None && supports_flush_control(extensions) {
+        context_attributes.push(ffi::egl::CONTEXT_RELEASE_BEHAVIOR_KHR as i32);
+        context_attributes.push(ffi::egl::CONTEXT_RELEASE_BEHAVIOR_NONE_KHR as i32);
     }
 
     context_attributes.push(ffi::egl::NONE as i32);
 
-    let context = egl.CreateContext(display, config_id, ptr::null(),
-                                    context_attributes.as_ptr());
+    let context = egl.CreateContext(display, config_id, share, context_attributes.as_ptr());
 
     if context.is_null() {
         match egl.GetError() as u32 {
@@ -777,5 +1337,32 @@ unsafe fn create_context(egl: &ffi::egl::Egl, display: ffi::egl::types::EGLDispl
         }
     }
 
-    Ok(context)
+    Ok((context, info))
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use platform::GlxOrEgl;
+
+    // needs an EGL driver that provides pbuffers
+    #[test]
+    #[ignore]
+    fn test_recreate_moves_surface() {
+        let egl = GlxOrEgl::new().egl.expect("libEGL not found");
+        let opengl: GlAttributes<&Context> = GlAttributes::default();
+        let context = Context::new(egl, &PixelFormatRequirements::default(), &opengl,
+                                   NativeDisplay::Other(None), SurfaceType::PBuffer)
+            .and_then(|prototype| prototype.finish_pbuffer((1, 1)))
+            .unwrap();
+
+        let recreated = context.recreate(&opengl).unwrap();
+        match context.swap_buffers() {
+            Err(ContextError::ContextLost) => (),
+            other => panic!("expected `ContextError::ContextLost`, got {:?}", other),
+        }
+
+        unsafe { recreated.make_current().unwrap() };
+        recreated.swap_buffers().unwrap();
+    }
 }
@@ -0,0 +1,45 @@
+//! OpenGL functions that glutin calls itself, for example to manage offscreen framebuffers.
+//!
+//! They must be loaded with the `get_proc_address` of the context they are used with.
+
+include!(concat!(env!("OUT_DIR"), "/internal_gl_bindings.rs"));
+
+use Api;
+
+use self::types::{GLenum, GLint};
+
+use std::ffi::CStr;
+
+/// Returns the value of an integer state variable. The context must be current.
+#[inline]
+pub unsafe fn get_integer(gl: &Gl, name: GLenum) -> GLint {
+    let mut value = 0;
+    gl.GetIntegerv(name, &mut value);
+    value
+}
+
+/// Returns the API and the version of the current context, by parsing `GL_VERSION`.
+///
+/// The context must be current.
+pub unsafe fn get_version(gl: &Gl) -> (Api, (u8, u8)) {
+    let version = gl.GetString(VERSION);
+    if version.is_null() {
+        return (Api::OpenGl, (1, 0));
+    }
+    let version = CStr::from_ptr(version as *const _).to_string_lossy();
+
+    // desktop OpenGL starts with the version number, OpenGL ES with "OpenGL ES"
+    let (api, version) = if version.starts_with("OpenGL ES") {
+        let version = version.trim_left_matches("OpenGL ES").trim_left_matches("-CM")
+                             .trim_left_matches("-CL").trim_left();
+        (Api::OpenGlEs, version.to_owned())
+    } else {
+        (Api::OpenGl, version.into_owned())
+    };
+
+    let mut numbers = version.split(|c: char| c == '.' || c == ' ')
+                             .map(|n| n.parse::<u8>().unwrap_or(0));
+    let major = numbers.next().unwrap_or(1);
+    let minor = numbers.next().unwrap_or(0);
+    (api, (major, minor))
+}
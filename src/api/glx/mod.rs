@@ -1,7 +1,13 @@
 #![cfg(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd", target_os = "openbsd"))]
 
+use ConfigBackend;
+use ConfigDescriptor;
+use ConfigId;
+use select_config;
 use ContextError;
+use ContextInfo;
 use CreationError;
+use Extensions;
 use GlAttributes;
 use GlProfile;
 use GlRequest;
@@ -11,10 +17,13 @@ use PixelFormatRequirements;
 use ReleaseBehavior;
 use Robustness;
 
+use api::gl;
+
 use libc;
 use libc::c_int;
 use std::ffi::{CStr, CString};
 use std::{mem, ptr, slice};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
 pub mod ffi {
     pub use x11_dl::xlib::*;
@@ -31,12 +40,31 @@ pub mod ffi {
     }
 }
  
+/// Specifies the kind of drawable the context is going to render to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SurfaceType {
+    /// A native window, see `ContextPrototype::finish`.
+    Window,
+    /// An offscreen pbuffer, see `ContextPrototype::finish_pbuffer`.
+    PBuffer,
+}
+
 pub struct Context {
     glx: ffi::glx::Glx,
     display: *mut ffi::Display,
-    window: ffi::Window,
+    // the window or the pbuffer, not a `Cell` as the pbuffer can be replaced from any thread
+    window: AtomicUsize,
+    surface_type: SurfaceType,
+    fb_config: ffi::glx::types::GLXFBConfig,
     context: ffi::GLXContext,
     pixel_format: PixelFormat,
+    info: ContextInfo,
+    extensions: Extensions,
+    vsync: Option<bool>,
+    // `glGetGraphicsResetStatusARB`, if the context is notified of resets
+    get_reset_status: Option<extern "system" fn() -> gl::types::GLenum>,
+    // set once a reset was detected, as GLX itself doesn't report them
+    lost: AtomicBool,
 }
 
 // TODO: remove me
@@ -55,6 +83,7 @@ impl Context {
         display: *mut ffi::Display,
         screen_id: libc::c_int,
         transparent: bool,
+        surface_type: SurfaceType,
     ) -> Result<ContextPrototype<'a>, CreationError>
     {
         // This is completely ridiculous, but VirtualBox's OpenGL driver needs some call handled by
@@ -78,20 +107,13 @@ impl Context {
 
         // finding the pixel format we want
         let (fb_config, pixel_format) = unsafe {
-            try!(choose_fbconfig(&glx, &extensions, xlib, display, screen_id, pf_reqs, transparent)
+            try!(choose_fbconfig(&glx, &extensions, xlib, display, screen_id, pf_reqs, transparent,
+                                 surface_type)
                                           .map_err(|_| CreationError::NoAvailablePixelFormat))
         };
 
         // getting the visual infos
-        let visual_infos: ffi::glx::types::XVisualInfo = unsafe {
-            let vi = glx.GetVisualFromFBConfig(display as *mut _, fb_config);
-            if vi.is_null() {
-                return Err(CreationError::OsError(format!("glxGetVisualFromFBConfig failed")));
-            }
-            let vi_copy = ptr::read(vi as *const _);
-            (xlib.XFree)(vi as *mut _);
-            vi_copy
-        };
+        let visual_infos = unsafe { get_visual_infos(&glx, xlib, display, fb_config)? };
 
         Ok(ContextPrototype {
             glx: glx,
@@ -100,18 +122,53 @@ impl Context {
             opengl: opengl,
             display: display,
             fb_config: fb_config,
-            visual_infos: unsafe { mem::transmute(visual_infos) },
+            visual_infos: visual_infos,
             pixel_format: pixel_format,
+            surface_type: surface_type,
         })
     }
 
+    /// Creates a new context that renders to the same window with the same framebuffer
+    /// configuration, for example to replace a context that was lost. `opengl.sharing` is
+    /// usually `None`, as the objects of a lost context are lost as well.
+    ///
+    /// # Panic
+    ///
+    /// Panics if the context renders to a pbuffer.
+    pub fn recreate<'a>(&self, xlib: &'a ffi::Xlib, opengl: &'a GlAttributes<&'a Context>)
+                        -> Result<Context, CreationError>
+    {
+        assert_eq!(self.surface_type, SurfaceType::Window, "only window contexts can be recreated");
+
+        let visual_infos = unsafe {
+            get_visual_infos(&self.glx, xlib, self.display, self.fb_config)?
+        };
+
+        let prototype = ContextPrototype {
+            glx: self.glx.clone(),
+            extensions: self.extensions.iter().cloned().collect::<Vec<_>>().join(" "),
+            xlib: xlib,
+            opengl: opengl,
+            display: self.display,
+            fb_config: self.fb_config,
+            visual_infos: visual_infos,
+            pixel_format: self.pixel_format.clone(),
+            surface_type: SurfaceType::Window,
+        };
+        prototype.finish(self.drawable())
+    }
+
+    #[inline]
+    fn drawable(&self) -> ffi::Window {
+        self.window.load(Ordering::SeqCst) as ffi::Window
+    }
+
     pub unsafe fn make_current(&self) -> Result<(), ContextError> {
-        // TODO: glutin needs some internal changes for proper error recovery
-        let res = self.glx.MakeCurrent(self.display as *mut _, self.window, self.context);
+        let res = self.glx.MakeCurrent(self.display as *mut _, self.drawable(), self.context);
         if res == 0 {
-            panic!("glx::MakeCurrent failed");
+            return Err(ContextError::OsError("glXMakeCurrent failed".to_owned()));
         }
-        Ok(())
+        self.check_reset()
     }
 
     #[inline]
@@ -129,11 +186,31 @@ impl Context {
 
     #[inline]
     pub fn swap_buffers(&self) -> Result<(), ContextError> {
-        // TODO: glutin needs some internal changes for proper error recovery
-        unsafe { self.glx.SwapBuffers(self.display as *mut _, self.window); }
+        self.check_reset()?;
+        unsafe { self.glx.SwapBuffers(self.display as *mut _, self.drawable()); }
         Ok(())
     }
 
+    /// Returns `ContextError::ContextLost` if the context was reset.
+    ///
+    /// Contrary to EGL, GLX functions don't fail on a lost context, so the reset status of the
+    /// context is queried when it is current, which clears it.
+    fn check_reset(&self) -> Result<(), ContextError> {
+        if let Some(get_reset_status) = self.get_reset_status {
+            if !self.lost.load(Ordering::SeqCst) && self.is_current() &&
+               get_reset_status() != gl::NO_ERROR
+            {
+                self.lost.store(true, Ordering::SeqCst);
+            }
+        }
+
+        if self.lost.load(Ordering::SeqCst) {
+            Err(ContextError::ContextLost)
+        } else {
+            Ok(())
+        }
+    }
+
     #[inline]
     pub fn get_api(&self) -> ::Api {
         ::Api::OpenGl
@@ -144,10 +221,50 @@ impl Context {
         self.pixel_format.clone()
     }
 
+    #[inline]
+    pub fn get_context_info(&self) -> ContextInfo {
+        self.info.clone()
+    }
+
+    #[inline]
+    pub fn get_extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    /// Returns whether vsync was enabled, or `None` if it wasn't requested.
+    #[inline]
+    pub fn get_vsync(&self) -> Option<bool> {
+        self.vsync
+    }
+
     #[inline]
     pub unsafe fn raw_handle(&self) -> ffi::GLXContext {
         self.context
     }
+
+    /// Replaces the pbuffer of the context with a new one of the given dimensions, using the
+    /// same fbconfig. If the context is current, the new pbuffer is made current.
+    ///
+    /// The content of the old pbuffer is lost. On error, for example if the dimensions are
+    /// above `GLX_MAX_PBUFFER_WIDTH` or `GLX_MAX_PBUFFER_HEIGHT`, the old pbuffer is kept.
+    pub unsafe fn resize_pbuffer(&self, xlib: &ffi::Xlib, dimensions: (u32, u32))
+                                 -> Result<(), CreationError>
+    {
+        assert!(self.surface_type == SurfaceType::PBuffer);
+
+        let pbuffer = create_pbuffer(&self.glx, xlib, self.display, self.fb_config, dimensions)?;
+
+        if self.is_current() &&
+           self.glx.MakeCurrent(self.display as *mut _, pbuffer, self.context) == 0
+        {
+            self.glx.DestroyPbuffer(self.display as *mut _, pbuffer);
+            return Err(CreationError::OsError("glXMakeCurrent failed".to_owned()));
+        }
+
+        let old_pbuffer = self.window.swap(pbuffer as usize, Ordering::SeqCst) as ffi::Window;
+        self.glx.DestroyPbuffer(self.display as *mut _, old_pbuffer);
+        Ok(())
+    }
 }
 
 unsafe impl Send for Context {}
@@ -161,6 +278,10 @@ impl Drop for Context {
             }
 
             self.glx.DestroyContext(self.display as *mut _, self.context);
+
+            if self.surface_type == SurfaceType::PBuffer {
+                self.glx.DestroyPbuffer(self.display as *mut _, self.drawable());
+            }
         }
     }
 }
@@ -174,6 +295,7 @@ pub struct ContextPrototype<'a> {
     fb_config: ffi::glx::types::GLXFBConfig,
     visual_infos: ffi::XVisualInfo,
     pixel_format: PixelFormat,
+    surface_type: SurfaceType,
 }
 
 impl<'a> ContextPrototype<'a> {
@@ -183,6 +305,17 @@ impl<'a> ContextPrototype<'a> {
     }
 
     pub fn finish(self, window: ffi::Window) -> Result<Context, CreationError> {
+        self.finish_impl(window)
+    }
+
+    pub fn finish_pbuffer(self, dimensions: (u32, u32)) -> Result<Context, CreationError> {
+        let pbuffer = unsafe {
+            create_pbuffer(&self.glx, self.xlib, self.display, self.fb_config, dimensions)?
+        };
+        self.finish_impl(pbuffer)
+    }
+
+    fn finish_impl(self, window: ffi::Window) -> Result<Context, CreationError> {
         let share = match self.opengl.sharing {
             Some(ctxt) => ctxt.context,
             None => ptr::null()
@@ -196,7 +329,7 @@ impl<'a> ContextPrototype<'a> {
         });
 
         // creating GL context
-        let context = match self.opengl.version {
+        let (context, mut info) = match self.opengl.version {
             GlRequest::Latest => {
                 let opengl_versions = [(4, 6), (4, 5), (4, 4), (4, 3), (4, 2), (4, 1), (4, 0),
                                        (3, 3), (3, 2), (3, 1)];
@@ -209,7 +342,8 @@ impl<'a> ContextPrototype<'a> {
                     {
                         match create_context(&self.glx, &extra_functions, &self.extensions, &self.xlib,
                                              *opengl_version, self.opengl.profile,
-                                             self.opengl.debug, self.opengl.robustness, share,
+                                             self.opengl.forward_compatible, self.opengl.debug,
+                                             self.opengl.robustness, share,
                                              self.display, self.fb_config, &self.visual_infos)
                         {
                             Ok(x) => {
@@ -220,8 +354,8 @@ impl<'a> ContextPrototype<'a> {
                         }
                     }
                     ctxt = try!(create_context(&self.glx, &extra_functions, &self.extensions, &self.xlib, (1, 0),
-                                               self.opengl.profile, self.opengl.debug,
-                                               self.opengl.robustness, share,
+                                               self.opengl.profile, self.opengl.forward_compatible,
+                                               self.opengl.debug, self.opengl.robustness, share,
                                                self.display, self.fb_config, &self.visual_infos));
                     break;
                 }
@@ -229,21 +363,43 @@ impl<'a> ContextPrototype<'a> {
             },
             GlRequest::Specific(Api::OpenGl, (major, minor)) => {
                 try!(create_context(&self.glx, &extra_functions, &self.extensions, &self.xlib, (major, minor),
-                                    self.opengl.profile, self.opengl.debug,
-                                    self.opengl.robustness, share, self.display, self.fb_config,
+                                    self.opengl.profile, self.opengl.forward_compatible,
+                                    self.opengl.debug, self.opengl.robustness, share,
+                                    self.display, self.fb_config,
                                     &self.visual_infos))
             },
             GlRequest::Specific(_, _) => panic!("Only OpenGL is supported"),
             GlRequest::GlThenGles { opengl_version: (major, minor), .. } => {
                 try!(create_context(&self.glx, &extra_functions, &self.extensions, &self.xlib, (major, minor),
-                                    self.opengl.profile, self.opengl.debug,
-                                    self.opengl.robustness, share, self.display, self.fb_config,
+                                    self.opengl.profile, self.opengl.forward_compatible,
+                                    self.opengl.debug, self.opengl.robustness, share,
+                                    self.display, self.fb_config,
                                     &self.visual_infos))
             },
         };
 
+        // drivers may provide a later version than the one requested
+        if let Some(version) = unsafe { query_version(&self.glx, self.display, window, context) } {
+            info.version = version;
+        }
+
+        // `GLX_ARB_create_context_robustness` requires `GL_ARB_robustness`
+        let get_reset_status = if info.robustness == Robustness::RobustLoseContextOnReset {
+            let address = with_c_str("glGetGraphicsResetStatusARB", |s| {
+                unsafe { self.glx.GetProcAddress(s as *const u8) as *const libc::c_void }
+            });
+            if address.is_null() {
+                None
+            } else {
+                Some(unsafe { mem::transmute(address) })
+            }
+        } else {
+            None
+        };
+
         // vsync
-        if self.opengl.vsync {
+        let mut vsync = None;
+        if self.opengl.vsync && self.surface_type == SurfaceType::Window {
             unsafe { self.glx.MakeCurrent(self.display as *mut _, window, context) };
 
             if check_ext(&self.extensions, "GLX_EXT_swap_control") && extra_functions.SwapIntervalEXT.is_loaded() {
@@ -253,20 +409,13 @@ impl<'a> ContextPrototype<'a> {
                 }
 
                 // checking that it worked
-                // TODO: handle this
-                /*if self.builder.strict {
-                    let mut swap = unsafe { mem::uninitialized() };
-                    unsafe {
-                        self.glx.QueryDrawable(self.display as *mut _, window,
-                                               ffi::glx_extra::SWAP_INTERVAL_EXT as i32,
-                                               &mut swap);
-                    }
-
-                    if swap != 1 {
-                        return Err(CreationError::OsError(format!("Couldn't setup vsync: expected \
-                                                    interval `1` but got `{}`", swap)));
-                    }
-                }*/
+                let mut swap = 0;
+                unsafe {
+                    self.glx.QueryDrawable(self.display as *mut _, window,
+                                           ffi::glx_extra::SWAP_INTERVAL_EXT as i32,
+                                           &mut swap);
+                }
+                vsync = Some(swap == 1);
 
             // GLX_MESA_swap_control is not official
             /*} else if extra_functions.SwapIntervalMESA.is_loaded() {
@@ -275,14 +424,15 @@ impl<'a> ContextPrototype<'a> {
                 }*/
 
             } else if check_ext(&self.extensions, "GLX_SGI_swap_control") && extra_functions.SwapIntervalSGI.is_loaded() {
-                unsafe {
-                    extra_functions.SwapIntervalSGI(1);
-                }
+                let ret = unsafe {
+                    extra_functions.SwapIntervalSGI(1)
+                };
+                vsync = Some(ret == 0);
 
-            }/* else if self.builder.strict {
-                // TODO: handle this
-                return Err(CreationError::OsError(format!("Couldn't find any available vsync extension")));
-            }*/
+            } else {
+                // no vsync extension is available
+                vsync = Some(false);
+            }
 
             unsafe { self.glx.MakeCurrent(self.display as *mut _, 0, ptr::null()) };
         }
@@ -290,13 +440,48 @@ impl<'a> ContextPrototype<'a> {
         Ok(Context {
             glx: self.glx,
             display: self.display,
-            window: window,
+            window: AtomicUsize::new(window as usize),
+            surface_type: self.surface_type,
+            fb_config: self.fb_config,
             context: context,
             pixel_format: self.pixel_format,
+            info: info,
+            extensions: Extensions::from_extension_string(&self.extensions),
+            vsync: vsync,
+            get_reset_status: get_reset_status,
+            lost: AtomicBool::new(false),
         })
     }
 }
 
+/// Makes `context` current to read its `GL_VERSION`, then makes the previously current context
+/// of the thread current again. Returns `None` if `context` couldn't be made current.
+unsafe fn query_version(glx: &ffi::glx::Glx, display: *mut ffi::Display, drawable: ffi::Window,
+                        context: ffi::GLXContext) -> Option<(u8, u8)>
+{
+    let previous_display = glx.GetCurrentDisplay();
+    let previous_context = glx.GetCurrentContext();
+    let previous_draw = glx.GetCurrentDrawable();
+    let previous_read = glx.GetCurrentReadDrawable();
+
+    if glx.MakeCurrent(display as *mut _, drawable, context) == 0 {
+        return None;
+    }
+
+    let gl = gl::Gl::load_with(|symbol| {
+        with_c_str(symbol, |s| glx.GetProcAddress(s as *const u8) as *const _)
+    });
+    let (_, version) = gl::get_version(&gl);
+
+    if previous_context.is_null() {
+        glx.MakeCurrent(display as *mut _, 0, ptr::null());
+    } else {
+        glx.MakeContextCurrent(previous_display, previous_draw, previous_read, previous_context);
+    }
+
+    Some(version)
+}
+
 extern fn x_error_callback(_dpy: *mut ffi::Display, _err: *mut ffi::XErrorEvent) -> i32
 {
     0
@@ -304,15 +489,15 @@ extern fn x_error_callback(_dpy: *mut ffi::Display, _err: *mut ffi::XErrorEvent)
 
 
 fn create_context(glx: &ffi::glx::Glx, extra_functions: &ffi::glx_extra::Glx, extensions: &str, xlib: &ffi::Xlib,
-                  version: (u8, u8), profile: Option<GlProfile>, debug: bool,
-                  robustness: Robustness, share: ffi::GLXContext, display: *mut ffi::Display,
+                  version: (u8, u8), profile: Option<GlProfile>, forward_compatible: bool,
+                  debug: bool, robustness: Robustness, share: ffi::GLXContext, display: *mut ffi::Display,
                   fb_config: ffi::glx::types::GLXFBConfig,
                   visual_infos: &ffi::XVisualInfo)
-                  -> Result<ffi::GLXContext, CreationError>
+                  -> Result<(ffi::GLXContext, ContextInfo), CreationError>
 {
     unsafe {
         let old_callback = (xlib.XSetErrorHandler)(Some(x_error_callback));
-        let context = if check_ext(extensions, "GLX_ARB_create_context") {
+        let (context, info) = if check_ext(extensions, "GLX_ARB_create_context") {
             let mut attributes = Vec::with_capacity(9);
 
             attributes.push(ffi::glx_extra::CONTEXT_MAJOR_VERSION_ARB as c_int);
@@ -332,6 +517,7 @@ fn create_context(glx: &ffi::glx::Glx, extra_functions: &ffi::glx_extra::Glx, ex
                 attributes.push(flag as c_int);
             }
 
+            let mut robustness_enabled = Robustness::NotRobust;
             let flags = {
                 let mut flags = 0;
 
@@ -342,11 +528,13 @@ fn create_context(glx: &ffi::glx::Glx, extra_functions: &ffi::glx_extra::Glx, ex
                             attributes.push(ffi::glx_extra::CONTEXT_RESET_NOTIFICATION_STRATEGY_ARB as c_int);
                             attributes.push(ffi::glx_extra::NO_RESET_NOTIFICATION_ARB as c_int);
                             flags = flags | ffi::glx_extra::CONTEXT_ROBUST_ACCESS_BIT_ARB as c_int;
+                            robustness_enabled = Robustness::RobustNoResetNotification;
                         },
                         Robustness::RobustLoseContextOnReset | Robustness::TryRobustLoseContextOnReset => {
                             attributes.push(ffi::glx_extra::CONTEXT_RESET_NOTIFICATION_STRATEGY_ARB as c_int);
                             attributes.push(ffi::glx_extra::LOSE_CONTEXT_ON_RESET_ARB as c_int);
                             flags = flags | ffi::glx_extra::CONTEXT_ROBUST_ACCESS_BIT_ARB as c_int;
+                            robustness_enabled = Robustness::RobustLoseContextOnReset;
                         },
                         Robustness::NotRobust => (),
                         Robustness::NoError => (),
@@ -360,6 +548,10 @@ fn create_context(glx: &ffi::glx::Glx, extra_functions: &ffi::glx_extra::Glx, ex
                     }
                 }
 
+                if forward_compatible {
+                    flags = flags | ffi::glx_extra::CONTEXT_FORWARD_COMPATIBLE_BIT_ARB as c_int;
+                }
+
                 if debug {
                     flags = flags | ffi::glx_extra::CONTEXT_DEBUG_BIT_ARB as c_int;
                 }
@@ -372,12 +564,34 @@ fn create_context(glx: &ffi::glx::Glx, extra_functions: &ffi::glx_extra::Glx, ex
 
             attributes.push(0);
 
-            extra_functions.CreateContextAttribsARB(display as *mut _, fb_config, share, 1,
-                                                    attributes.as_ptr())
+            let context = extra_functions.CreateContextAttribsARB(display as *mut _, fb_config,
+                                                                  share, 1, attributes.as_ptr());
+
+            // profiles only exist since OpenGL 3.2, and the core profile is the default one
+            let info = ContextInfo {
+                version: version,
+                profile: if version >= (3, 2) { Some(profile.unwrap_or(GlProfile::Core)) }
+                         else { None },
+                debug: debug,
+                forward_compatible: forward_compatible,
+                robustness: robustness_enabled,
+            };
+
+            (context, info)
 
         } else {
             let visual_infos: *const ffi::XVisualInfo = visual_infos;
-            glx.CreateContext(display as *mut _, visual_infos as *mut _, share, 1)
+            let context = glx.CreateContext(display as *mut _, visual_infos as *mut _, share, 1);
+
+            let info = ContextInfo {
+                version: (1, 0),
+                profile: None,
+                debug: false,
+                forward_compatible: false,
+                robustness: Robustness::NotRobust,
+            };
+
+            (context, info)
         };
         
         (xlib.XSetErrorHandler)(old_callback);
@@ -387,17 +601,75 @@ fn create_context(glx: &ffi::glx::Glx, extra_functions: &ffi::glx_extra::Glx, ex
             return Err(CreationError::OsError(format!("GL context creation failed")));
         }
 
-        Ok(context)
+        Ok((context, info))
     }
 }
 
+/// Returns the visual of a framebuffer configuration.
+unsafe fn get_visual_infos(glx: &ffi::glx::Glx, xlib: &ffi::Xlib, display: *mut ffi::Display,
+                           fb_config: ffi::glx::types::GLXFBConfig)
+                           -> Result<ffi::XVisualInfo, CreationError>
+{
+    let vi = glx.GetVisualFromFBConfig(display as *mut _, fb_config);
+    if vi.is_null() {
+        return Err(CreationError::OsError(format!("glxGetVisualFromFBConfig failed")));
+    }
+    let vi_copy: ffi::glx::types::XVisualInfo = ptr::read(vi as *const _);
+    (xlib.XFree)(vi as *mut _);
+    Ok(mem::transmute(vi_copy))
+}
+
+/// Creates a pbuffer of the given dimensions with `fb_config`.
+///
+/// The X errors raised by the creation, for example if the dimensions are too large, are
+/// returned instead of going through the default error handler, which would exit the process.
+unsafe fn create_pbuffer(glx: &ffi::glx::Glx, xlib: &ffi::Xlib, display: *mut ffi::Display,
+                         fb_config: ffi::glx::types::GLXFBConfig, dimensions: (u32, u32))
+                         -> Result<ffi::glx::types::GLXPbuffer, CreationError>
+{
+    let attributes = [
+        ffi::glx::PBUFFER_WIDTH as c_int, dimensions.0 as c_int,
+        ffi::glx::PBUFFER_HEIGHT as c_int, dimensions.1 as c_int,
+        0,
+    ];
+
+    let old_callback = (xlib.XSetErrorHandler)(Some(pbuffer_error_callback));
+    PBUFFER_ERROR.store(false, Ordering::SeqCst);
+    let pbuffer = glx.CreatePbuffer(display as *mut _, fb_config, attributes.as_ptr());
+    (xlib.XSync)(display, 0);
+    (xlib.XSetErrorHandler)(old_callback);
+
+    // the id is allocated by Xlib, so it isn't 0 when the server fails to create the pbuffer
+    if pbuffer == 0 || PBUFFER_ERROR.load(Ordering::SeqCst) {
+        return Err(CreationError::OsError("glXCreatePbuffer failed".to_owned()));
+    }
+    Ok(pbuffer)
+}
+
+// set by `pbuffer_error_callback` when an X error is raised while creating a pbuffer
+static PBUFFER_ERROR: AtomicBool = AtomicBool::new(false);
+
+extern fn pbuffer_error_callback(_dpy: *mut ffi::Display, _err: *mut ffi::XErrorEvent) -> i32 {
+    PBUFFER_ERROR.store(true, Ordering::SeqCst);
+    0
+}
+
 /// Enumerates all available FBConfigs
 unsafe fn choose_fbconfig(glx: &ffi::glx::Glx, extensions: &str, xlib: &ffi::Xlib,
                           display: *mut ffi::Display, screen_id: libc::c_int,
-                          reqs: &PixelFormatRequirements, transparent: bool)
+                          reqs: &PixelFormatRequirements, transparent: bool,
+                          surface_type: SurfaceType)
                           -> Result<(ffi::glx::types::GLXFBConfig, PixelFormat), ()>
 {
-    let descriptor = {
+    let descriptor = if let Some(id) = reqs.config_id {
+        if id.backend != ConfigBackend::Glx {
+            return Err(());
+        }
+
+        // the other attributes are ignored when `GLX_FBCONFIG_ID` is given
+        vec![ffi::glx::FBCONFIG_ID as c_int, id.id, 0]
+
+    } else {
         let mut out: Vec<c_int> = Vec::with_capacity(37);
 
         out.push(ffi::glx::X_RENDERABLE as c_int);
@@ -407,7 +679,10 @@ unsafe fn choose_fbconfig(glx: &ffi::glx::Glx, extensions: &str, xlib: &ffi::Xli
         out.push(ffi::glx::TRUE_COLOR as c_int);
 
         out.push(ffi::glx::DRAWABLE_TYPE as c_int);
-        out.push(ffi::glx::WINDOW_BIT as c_int);
+        out.push(match surface_type {
+            SurfaceType::Window => ffi::glx::WINDOW_BIT as c_int,
+            SurfaceType::PBuffer => ffi::glx::PBUFFER_BIT as c_int,
+        });
 
         out.push(ffi::glx::RENDER_TYPE as c_int);
         if reqs.float_color_buffer {
@@ -420,13 +695,13 @@ unsafe fn choose_fbconfig(glx: &ffi::glx::Glx, extensions: &str, xlib: &ffi::Xli
             out.push(ffi::glx::RGBA_BIT as c_int);
         }
 
-        if let Some(color) = reqs.color_bits {
+        if let Some((red, green, blue)) = reqs.rgb_bits() {
             out.push(ffi::glx::RED_SIZE as c_int);
-            out.push((color / 3) as c_int);
+            out.push(red as c_int);
             out.push(ffi::glx::GREEN_SIZE as c_int);
-            out.push((color / 3 + if color % 3 != 0 { 1 } else { 0 }) as c_int);
+            out.push(green as c_int);
             out.push(ffi::glx::BLUE_SIZE as c_int);
-            out.push((color / 3 + if color % 3 == 2 { 1 } else { 0 }) as c_int);
+            out.push(blue as c_int);
         }
 
         if let Some(alpha) = reqs.alpha_bits {
@@ -444,7 +719,23 @@ unsafe fn choose_fbconfig(glx: &ffi::glx::Glx, extensions: &str, xlib: &ffi::Xli
             out.push(stencil as c_int);
         }
 
-        let double_buffer = reqs.double_buffer.unwrap_or(true);
+        if let Some(accum) = reqs.accum_bits {
+            out.push(ffi::glx::ACCUM_RED_SIZE as c_int);
+            out.push(accum as c_int);
+            out.push(ffi::glx::ACCUM_GREEN_SIZE as c_int);
+            out.push(accum as c_int);
+            out.push(ffi::glx::ACCUM_BLUE_SIZE as c_int);
+            out.push(accum as c_int);
+        }
+
+        if let Some(aux_buffers) = reqs.aux_buffers {
+            out.push(ffi::glx::AUX_BUFFERS as c_int);
+            out.push(aux_buffers as c_int);
+        }
+
+        // there is nobody to present the back buffer of a pbuffer, so single buffering is
+        // preferred for them
+        let double_buffer = reqs.double_buffer.unwrap_or(surface_type == SurfaceType::Window);
         out.push(ffi::glx::DOUBLEBUFFER as c_int);
         out.push(if double_buffer { 1 } else { 0 });
 
@@ -493,36 +784,66 @@ unsafe fn choose_fbconfig(glx: &ffi::glx::Glx, extensions: &str, xlib: &ffi::Xli
 
     // calling glXChooseFBConfig
     let fb_config = {
-        let mut num_configs = 1;
+        let mut num_configs = 0;
         let configs = glx.ChooseFBConfig(display as *mut _, screen_id, descriptor.as_ptr(),
                                         &mut num_configs);
         if configs.is_null() { return Err(()); }
-        if num_configs == 0 { return Err(()); }
 
-        let config = if transparent {
-            let configs = slice::from_raw_parts(configs, num_configs as usize);
-            configs.iter().find(|&config| {
-                let vi = glx.GetVisualFromFBConfig(display as *mut _, *config);
+        let candidates: Vec<_> = slice::from_raw_parts(configs, num_configs as usize).iter()
+            .cloned()
+            .filter(|&config| {
+                if !transparent {
+                    return true;
+                }
+
+                let vi = glx.GetVisualFromFBConfig(display as *mut _, config);
                 // Transparency was requested, so only choose configs with 32 bits for RGBA.
                 let found = !vi.is_null() && (*vi).depth == 32;
                 (xlib.XFree)(vi as *mut _);
 
                 found
             })
-        } else {
-            Some(&*configs)
-        };
+            .collect();
+        (xlib.XFree)(configs as *mut _);
 
-        let res = if let Some(&conf) = config {
-            Ok(conf)
+        if reqs.config_id.is_some() {
+            *candidates.first().ok_or(())?
         } else {
-            Err(())
+            // the configs are sorted by criteria of GLX, for example more samples than requested
+            // come first, so the first one isn't necessarily the closest to the requirements
+            let descriptors: Vec<_> = candidates.iter()
+                .map(|&config| describe_fbconfig(glx, xlib, display, config))
+                .collect();
+            candidates[select_config(&descriptors, reqs).ok_or(())?]
+        }
+    };
+
+    if reqs.config_id.is_some() {
+        let mut drawable_bits = 0;
+        glx.GetFBConfigAttrib(display as *mut _, fb_config, ffi::glx::DRAWABLE_TYPE as c_int,
+                              &mut drawable_bits);
+        let required_bits = match surface_type {
+            SurfaceType::Window => ffi::glx::WINDOW_BIT as c_int,
+            SurfaceType::PBuffer => ffi::glx::PBUFFER_BIT as c_int,
         };
+        if drawable_bits & required_bits == 0 {
+            return Err(());
+        }
+    }
 
-        (xlib.XFree)(configs as *mut _);
-        res?
-    };
+    let mut pixel_format = fbconfig_pixel_format(glx, xlib, display, fb_config);
+    if reqs.release_behavior == ReleaseBehavior::None &&
+       check_ext(extensions, "GLX_ARB_context_flush_control")
+    {
+        pixel_format.release_behavior = ReleaseBehavior::None;
+    }
 
+    Ok((fb_config, pixel_format))
+}
+
+unsafe fn fbconfig_pixel_format(glx: &ffi::glx::Glx, xlib: &ffi::Xlib, display: *mut ffi::Display,
+                                fb_config: ffi::glx::types::GLXFBConfig) -> PixelFormat
+{
     let get_attrib = |attrib: c_int| -> i32 {
         let mut value = 0;
         glx.GetFBConfigAttrib(display as *mut _, fb_config, attrib, &mut value);
@@ -530,15 +851,34 @@ unsafe fn choose_fbconfig(glx: &ffi::glx::Glx, extensions: &str, xlib: &ffi::Xli
         value
     };
 
-    let pf_desc = PixelFormat {
+    // pbuffer-only configs don't have a visual
+    let visual_depth = {
+        let vi = glx.GetVisualFromFBConfig(display as *mut _, fb_config);
+        if vi.is_null() {
+            None
+        } else {
+            let depth = (*vi).depth;
+            (xlib.XFree)(vi as *mut _);
+            Some(depth)
+        }
+    };
+
+    let red_bits = get_attrib(ffi::glx::RED_SIZE as c_int) as u8;
+    let green_bits = get_attrib(ffi::glx::GREEN_SIZE as c_int) as u8;
+    let blue_bits = get_attrib(ffi::glx::BLUE_SIZE as c_int) as u8;
+
+    PixelFormat {
         hardware_accelerated: get_attrib(ffi::glx::CONFIG_CAVEAT as c_int) !=
                                                             ffi::glx::SLOW_CONFIG as c_int,
-        color_bits: get_attrib(ffi::glx::RED_SIZE as c_int) as u8 +
-                    get_attrib(ffi::glx::GREEN_SIZE as c_int) as u8 +
-                    get_attrib(ffi::glx::BLUE_SIZE as c_int) as u8,
+        color_bits: red_bits + green_bits + blue_bits,
+        red_bits: red_bits,
+        green_bits: green_bits,
+        blue_bits: blue_bits,
         alpha_bits: get_attrib(ffi::glx::ALPHA_SIZE as c_int) as u8,
         depth_bits: get_attrib(ffi::glx::DEPTH_SIZE as c_int) as u8,
         stencil_bits: get_attrib(ffi::glx::STENCIL_SIZE as c_int) as u8,
+        accum_bits: get_attrib(ffi::glx::ACCUM_RED_SIZE as c_int) as u8,
+        aux_buffers: get_attrib(ffi::glx::AUX_BUFFERS as c_int) as u8,
         stereoscopy: get_attrib(ffi::glx::STEREO as c_int) != 0,
         double_buffer: get_attrib(ffi::glx::DOUBLEBUFFER as c_int) != 0,
         multisampling: if get_attrib(ffi::glx::SAMPLE_BUFFERS as c_int) != 0 {
@@ -548,9 +888,69 @@ unsafe fn choose_fbconfig(glx: &ffi::glx::Glx, extensions: &str, xlib: &ffi::Xli
         },
         srgb: get_attrib(ffi::glx_extra::FRAMEBUFFER_SRGB_CAPABLE_ARB as c_int) != 0 ||
               get_attrib(ffi::glx_extra::FRAMEBUFFER_SRGB_CAPABLE_EXT as c_int) != 0,
-    };
+        float_color_buffer: get_attrib(ffi::glx::RENDER_TYPE as c_int) &
+                            ffi::glx_extra::RGBA_FLOAT_BIT_ARB as c_int != 0,
+        // the visual has an alpha channel that the compositor uses
+        transparent: visual_depth == Some(32),
+        release_behavior: ReleaseBehavior::Flush,
+        native_visual_id: match get_attrib(ffi::glx::VISUAL_ID as c_int) {
+            0 => None,
+            id => Some(id as u32),
+        },
+        config_id: Some(ConfigId {
+            backend: ConfigBackend::Glx,
+            id: get_attrib(ffi::glx::FBCONFIG_ID as c_int),
+        }),
+    }
+}
+
+/// Lists the RGBA framebuffer configurations of a screen.
+pub unsafe fn available_configs(glx: &ffi::glx::Glx, xlib: &ffi::Xlib, display: *mut ffi::Display,
+                                screen_id: c_int) -> Vec<ConfigDescriptor>
+{
+    let mut num_configs = 0;
+    let configs = glx.GetFBConfigs(display as *mut _, screen_id, &mut num_configs);
+    if configs.is_null() {
+        return Vec::new();
+    }
+
+    let mut descriptors = Vec::with_capacity(num_configs as usize);
+    for &config in slice::from_raw_parts(configs, num_configs as usize) {
+        let get_attrib = |attrib: c_int| -> i32 {
+            let mut value = 0;
+            glx.GetFBConfigAttrib(display as *mut _, config, attrib, &mut value);
+            value
+        };
+
+        // color index configs can't be described by a `PixelFormat`
+        let render_bits = get_attrib(ffi::glx::RENDER_TYPE as c_int);
+        let rgba_bits = ffi::glx::RGBA_BIT | ffi::glx_extra::RGBA_FLOAT_BIT_ARB;
+        if render_bits & rgba_bits as c_int == 0 {
+            continue;
+        }
+
+        descriptors.push(describe_fbconfig(glx, xlib, display, config));
+    }
 
-    Ok((fb_config, pf_desc))
+    (xlib.XFree)(configs as *mut _);
+    descriptors
+}
+
+unsafe fn describe_fbconfig(glx: &ffi::glx::Glx, xlib: &ffi::Xlib, display: *mut ffi::Display,
+                            fb_config: ffi::glx::types::GLXFBConfig) -> ConfigDescriptor
+{
+    let mut drawable_bits = 0;
+    glx.GetFBConfigAttrib(display as *mut _, fb_config, ffi::glx::DRAWABLE_TYPE as c_int,
+                          &mut drawable_bits);
+
+    let pixel_format = fbconfig_pixel_format(glx, xlib, display, fb_config);
+    ConfigDescriptor {
+        id: pixel_format.config_id.unwrap(),
+        float_color_buffer: pixel_format.float_color_buffer,
+        window: drawable_bits & ffi::glx::WINDOW_BIT as c_int != 0,
+        pbuffer: drawable_bits & ffi::glx::PBUFFER_BIT as c_int != 0,
+        pixel_format: pixel_format,
+    }
 }
 
 /// Checks if `ext` is available.
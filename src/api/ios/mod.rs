@@ -72,9 +72,13 @@ use PixelFormatRequirements;
 use GlAttributes;
 use CreationError;
 use ContextError;
+use ContextInfo;
+use Extensions;
 use WindowAttributes;
 use Api;
 use PixelFormat;
+use ReleaseBehavior;
+use Robustness;
 
 use std::os::raw::c_void;
 use std::{io, mem};
@@ -94,6 +98,8 @@ const VIEW_CLASS: &'static str = "MainView";
 pub struct Context {
     eagl_context: id,
     pixel_format: PixelFormat,
+    // EAGL doesn't have extensions
+    extensions: Extensions,
 }
 
 impl Context {
@@ -103,6 +109,9 @@ impl Context {
         pf_reqs: &PixelFormatRequirements,
         _gl_attr: &GlAttributes<&Self>,
     ) -> Result<(winit::Window, Self), CreationError> {
+        if pf_reqs.config_id.is_some() {
+            return Err(CreationError::NoAvailablePixelFormat);
+        }
         let attr = window_builder.window.clone();
         let window_winit = try!(window_builder.build(events_loop));
         let eagl_ctx = Context::create_context();
@@ -129,6 +138,7 @@ impl Context {
             let mut ctx = Context {
                 eagl_context: eagl_ctx,
                 pixel_format: pixel_format,
+                extensions: Extensions::default(),
             };
 
             ctx.init_context(&attr, view, scale);
@@ -255,10 +265,40 @@ impl Context {
         self.pixel_format.clone()
     }
 
+    #[inline]
+    pub fn get_context_info(&self) -> ContextInfo {
+        // see `create_context`
+        ContextInfo {
+            version: (3, 0),
+            profile: None,
+            debug: false,
+            forward_compatible: false,
+            robustness: Robustness::NotRobust,
+        }
+    }
+
+    #[inline]
+    pub fn get_platform_extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
     #[inline]
     pub fn resize(&self, _width: u32, _height: u32) {
         // No sense on iOS
     }
+
+    /// See the docs in the crate root file.
+    #[inline]
+    pub fn recreate(&self, _: &winit::Window, _: &GlAttributes<&Self>)
+                    -> Result<Self, CreationError>
+    {
+        Err(CreationError::NotSupported)
+    }
+
+    #[inline]
+    pub fn get_vsync(&self) -> Option<bool> {
+        None
+    }
 }
 
 fn create_uiview_class() {
@@ -306,12 +346,22 @@ fn create_pixel_format(reqs: &PixelFormatRequirements) -> PixelFormat {
     PixelFormat {
         hardware_accelerated: reqs.hardware_accelerated.unwrap_or(true),
         color_bits: reqs.color_bits.unwrap_or(24),
+        red_bits: reqs.color_bits.unwrap_or(24) / 3,
+        green_bits: reqs.color_bits.unwrap_or(24) / 3,
+        blue_bits: reqs.color_bits.unwrap_or(24) / 3,
         alpha_bits: reqs.alpha_bits.unwrap_or(8),
         depth_bits: reqs.depth_bits.unwrap_or(24),
         stencil_bits: reqs.stencil_bits.unwrap_or(8),
+        accum_bits: 0,
+        aux_buffers: 0,
         stereoscopy: reqs.stereoscopy,
         double_buffer: reqs.double_buffer.unwrap_or(true),
         multisampling: reqs.multisampling,
         srgb: reqs.srgb,
+        float_color_buffer: reqs.float_color_buffer,
+        transparent: false,
+        release_behavior: ReleaseBehavior::Flush,
+        native_visual_id: None,
+        config_id: None,
     }
 }
\ No newline at end of file
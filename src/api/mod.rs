@@ -2,6 +2,7 @@ pub mod android;
 pub mod caca;
 pub mod dlopen;
 pub mod egl;
+pub mod gl;
 pub mod glx;
 pub mod osmesa;
 pub mod wgl;
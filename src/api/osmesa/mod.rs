@@ -4,20 +4,25 @@ extern crate osmesa_sys;
 
 use Api;
 use ContextError;
+use ContextInfo;
 use CreationError;
+use Extensions;
 use GlAttributes;
 use GlProfile;
 use GlRequest;
 use PixelFormat;
 use PixelFormatRequirements;
+use ReleaseBehavior;
 use Robustness;
+use api::gl;
 use libc;
 
+use std::cmp;
 use std::error::Error;
 use std::ffi::CString;
 use std::fmt::{Debug, Display, Error as FormatError, Formatter};
 use std::{mem, ptr};
-use std::os::raw::c_void;
+use std::sync::{Mutex, MutexGuard};
 
 pub mod ffi {
     pub use super::osmesa_sys::OSMesaContext;
@@ -25,9 +30,25 @@ pub mod ffi {
 
 pub struct OsMesaContext {
     context: osmesa_sys::OSMesaContext,
-    buffer: Vec<u32>,
-    width: u32,
-    height: u32,
+    // not a `RefCell`, as the context can be resized from any thread
+    framebuffer: Mutex<Framebuffer>,
+    bytes_per_pixel: usize,
+    // the type of each channel in the buffer, passed to `OSMesaMakeCurrent`
+    gl_type: libc::c_uint,
+    pixel_format: PixelFormat,
+    info: ContextInfo,
+    // OSMesa doesn't have extensions
+    extensions: Extensions,
+}
+
+/// The memory that OSMesa renders to. Its layout depends on the pixel format.
+pub struct Framebuffer {
+    // `u32` so that the buffer is aligned for every type that OSMesa can write
+    pub data: Vec<u32>,
+    pub dimensions: (u32, u32),
+    // the buffer that OSMesa was last bound to, if it was replaced since, as the context may
+    // still be rendering to it on another thread
+    retired: Option<Vec<u32>>,
 }
 
 #[derive(Debug)]
@@ -69,7 +90,7 @@ impl Error for LoadingError {
 impl OsMesaContext {
     pub fn new(
         dimensions: (u32, u32),
-        _pf_reqs: &PixelFormatRequirements,
+        pf_reqs: &PixelFormatRequirements,
         opengl: &GlAttributes<&OsMesaContext>,
     ) -> Result<OsMesaContext, CreationError>
     {
@@ -77,8 +98,6 @@ impl OsMesaContext {
             .map_err(LoadingError::new)
             .map_err(|e| CreationError::NoBackendAvailable(Box::new(e)))?;
 
-        if opengl.sharing.is_some() { panic!("Context sharing not possible with OsMesa") }
-
         match opengl.robustness {
             Robustness::RobustNoResetNotification | Robustness::RobustLoseContextOnReset => {
                 return Err(CreationError::RobustnessNotSupported.into());
@@ -86,10 +105,66 @@ impl OsMesaContext {
             _ => ()
         }
 
-        // TODO: use `pf_reqs` for the format
+        // OSMesa only renders in software to a single-buffered, non-multisampled buffer, and
+        // doesn't have any configuration to choose from
+        if pf_reqs.hardware_accelerated == Some(true) || pf_reqs.double_buffer == Some(true) ||
+           pf_reqs.multisampling.unwrap_or(0) != 0 || pf_reqs.stereoscopy || pf_reqs.srgb ||
+           pf_reqs.config_id.is_some() || pf_reqs.aux_buffers.unwrap_or(0) != 0
+        {
+            return Err(CreationError::NoAvailablePixelFormat);
+        }
+
+        let (format, gl_type, bytes_per_pixel, color_bits, alpha_bits) = {
+            // every channel has the same size, except in RGB565
+            let color = match pf_reqs.channel_bits {
+                Some((red, green, blue)) => cmp::max(red, cmp::max(green, blue)).saturating_mul(3),
+                None => pf_reqs.color_bits.unwrap_or(24),
+            };
+            let alpha = pf_reqs.alpha_bits.unwrap_or(8);
+
+            match (pf_reqs.float_color_buffer, color, alpha) {
+                (true, 0...96, 0...32) => (osmesa_sys::OSMESA_RGBA, gl::FLOAT, 16, 96, 32),
+                (false, 0...16, 0) => {
+                    (osmesa_sys::OSMESA_RGB_565, gl::UNSIGNED_SHORT_5_6_5, 2, 16, 0)
+                },
+                (false, 0...24, 0) => (osmesa_sys::OSMESA_RGB, gl::UNSIGNED_BYTE, 3, 24, 0),
+                (false, 0...24, 0...8) => (osmesa_sys::OSMESA_RGBA, gl::UNSIGNED_BYTE, 4, 24, 8),
+                (false, 0...48, 0...16) => {
+                    (osmesa_sys::OSMESA_RGBA, gl::UNSIGNED_SHORT, 8, 48, 16)
+                },
+                _ => return Err(CreationError::NoAvailablePixelFormat),
+            }
+        };
+
+        // OSMesa only has a 24 bits depth buffer when a stencil buffer is requested
+        let (depth_bits, stencil_bits) = match (pf_reqs.depth_bits.unwrap_or(0),
+                                                pf_reqs.stencil_bits.unwrap_or(0))
+        {
+            (0, 0) => (0, 0),
+            (0...24, 1...8) => (24, 8),
+            (1...16, 0) => (16, 0),
+            (17...24, 0) => (24, 0),
+            (25...32, 0) => (32, 0),
+            _ => return Err(CreationError::NoAvailablePixelFormat),
+        };
+
+        let accum_bits = match pf_reqs.accum_bits.unwrap_or(0) {
+            0 => 0,
+            1...16 => 16,
+            _ => return Err(CreationError::NoAvailablePixelFormat),
+        };
 
         let mut attribs = Vec::new();
 
+        attribs.push(osmesa_sys::OSMESA_FORMAT);
+        attribs.push(format as libc::c_int);
+        attribs.push(osmesa_sys::OSMESA_DEPTH_BITS);
+        attribs.push(depth_bits as libc::c_int);
+        attribs.push(osmesa_sys::OSMESA_STENCIL_BITS);
+        attribs.push(stencil_bits as libc::c_int);
+        attribs.push(osmesa_sys::OSMESA_ACCUM_BITS);
+        attribs.push(accum_bits as libc::c_int);
+
         if let Some(profile) = opengl.profile {
             attribs.push(osmesa_sys::OSMESA_PROFILE);
 
@@ -103,13 +178,15 @@ impl OsMesaContext {
             }
         }
 
-        match opengl.version {
-            GlRequest::Latest => {},
+        // OSMesa defaults to version 1.0, which gives the latest compatibility profile
+        let version = match opengl.version {
+            GlRequest::Latest => (1, 0),
             GlRequest::Specific(Api::OpenGl, (major, minor)) => {
                 attribs.push(osmesa_sys::OSMESA_CONTEXT_MAJOR_VERSION);
                 attribs.push(major as libc::c_int);
                 attribs.push(osmesa_sys::OSMESA_CONTEXT_MINOR_VERSION);
                 attribs.push(minor as libc::c_int);
+                (major, minor)
             },
             GlRequest::Specific(Api::OpenGlEs, _) | GlRequest::Specific(Api::WebGl, _) => {
                 return Err(CreationError::NoBackendAvailable(Box::new(NoEsOrWebGlSupported)));
@@ -119,42 +196,114 @@ impl OsMesaContext {
                 attribs.push(major as libc::c_int);
                 attribs.push(osmesa_sys::OSMESA_CONTEXT_MINOR_VERSION);
                 attribs.push(minor as libc::c_int);
+                (major, minor)
             },
-        }
+        };
 
         // attribs array must be NULL terminated.
         attribs.push(0);
 
         Ok(OsMesaContext {
-            width: dimensions.0,
-            height: dimensions.1,
-            buffer: ::std::iter::repeat(unsafe { mem::uninitialized() })
-                .take((dimensions.0 * dimensions.1) as usize).collect(),
+            framebuffer: Mutex::new(Framebuffer {
+                data: allocate_buffer(dimensions, bytes_per_pixel),
+                dimensions: dimensions,
+                retired: None,
+            }),
+            bytes_per_pixel: bytes_per_pixel,
+            gl_type: gl_type,
             context: unsafe {
-                let ctxt = osmesa_sys::OSMesaCreateContextAttribs(attribs.as_ptr(), ptr::null_mut());
+                let share = match opengl.sharing {
+                    Some(ctxt) => ctxt.context,
+                    None => ptr::null_mut(),
+                };
+                let ctxt = osmesa_sys::OSMesaCreateContextAttribs(attribs.as_ptr(), share);
                 if ctxt.is_null() {
                     return Err(CreationError::OsError("OSMesaCreateContextAttribs failed".to_string()));
                 }
                 ctxt
-            }
+            },
+            pixel_format: PixelFormat {
+                hardware_accelerated: false,
+                color_bits: color_bits,
+                red_bits: if color_bits == 16 { 5 } else { color_bits / 3 },
+                green_bits: if color_bits == 16 { 6 } else { color_bits / 3 },
+                blue_bits: if color_bits == 16 { 5 } else { color_bits / 3 },
+                alpha_bits: alpha_bits,
+                depth_bits: depth_bits,
+                stencil_bits: stencil_bits,
+                accum_bits: accum_bits,
+                aux_buffers: 0,
+                stereoscopy: false,
+                double_buffer: false,
+                multisampling: None,
+                srgb: false,
+                float_color_buffer: pf_reqs.float_color_buffer,
+                transparent: false,
+                release_behavior: ReleaseBehavior::Flush,
+                native_visual_id: None,
+                config_id: None,
+            },
+            // OSMesa has no context flags, and the compatibility profile is the default one
+            info: ContextInfo {
+                version: version,
+                profile: if version >= (3, 2) {
+                    Some(opengl.profile.unwrap_or(GlProfile::Compatibility))
+                } else {
+                    None
+                },
+                debug: false,
+                forward_compatible: false,
+                robustness: Robustness::NotRobust,
+            },
+            extensions: Extensions::default(),
         })
     }
 
+    /// Returns the memory that OSMesa renders to, which stays locked until the guard is dropped.
     #[inline]
-    pub fn get_framebuffer(&self) -> &[u32] {
-        &self.buffer
+    pub fn get_framebuffer(&self) -> MutexGuard<Framebuffer> {
+        self.framebuffer.lock().unwrap()
     }
 
     #[inline]
     pub fn get_dimensions(&self) -> (u32, u32) {
-        (self.width, self.height)
+        self.framebuffer.lock().unwrap().dimensions
+    }
+
+    /// Replaces the buffer that OSMesa renders to with a new one of the given dimensions. If the
+    /// context is current on this thread, the new buffer is bound immediately. Otherwise, it is
+    /// bound the next time the context is made current.
+    ///
+    /// The content of the old buffer is lost.
+    pub fn resize(&self, dimensions: (u32, u32)) {
+        let mut framebuffer = self.framebuffer.lock().unwrap();
+        let old_data = mem::replace(&mut framebuffer.data,
+                                    allocate_buffer(dimensions, self.bytes_per_pixel));
+        framebuffer.dimensions = dimensions;
+
+        if self.is_current() {
+            unsafe { self.bind(&mut framebuffer); }
+        } else if framebuffer.retired.is_none() {
+            // `is_current` only knows about this thread, so the context may be current on
+            // another one and OSMesa may still render to the old buffer. It is freed once the
+            // new buffer is bound. If a buffer is already retired, the old one was never bound.
+            framebuffer.retired = Some(old_data);
+        }
     }
 
     #[inline]
     pub unsafe fn make_current(&self) -> Result<(), ContextError> {
-        let ret = osmesa_sys::OSMesaMakeCurrent(self.context, self.buffer.as_ptr()
-                                                as *mut _, 0x1401, self.width
-                                                as libc::c_int, self.height as libc::c_int);
+        self.bind(&mut self.framebuffer.lock().unwrap());
+        Ok(())
+    }
+
+    /// Makes the context current with `framebuffer`, which must be the one of the context, and
+    /// frees the buffer it replaced.
+    unsafe fn bind(&self, framebuffer: &mut Framebuffer) {
+        let (width, height) = framebuffer.dimensions;
+        let ret = osmesa_sys::OSMesaMakeCurrent(self.context, framebuffer.data.as_ptr()
+                                                as *mut _, self.gl_type, width as libc::c_int,
+                                                height as libc::c_int);
 
         // an error can only happen in case of invalid parameter, which would indicate a bug
         // in glutin
@@ -162,7 +311,12 @@ impl OsMesaContext {
             panic!("OSMesaMakeCurrent failed");
         }
 
-        Ok(())
+        // float buffers are clamped to `[0.0, 1.0]` by default
+        if self.gl_type == gl::FLOAT {
+            osmesa_sys::OSMesaColorClamp(0);
+        }
+
+        framebuffer.retired = None;
     }
 
     #[inline]
@@ -189,12 +343,22 @@ impl OsMesaContext {
 
     #[inline]
     pub fn get_pixel_format(&self) -> PixelFormat {
-        unimplemented!();
+        self.pixel_format.clone()
+    }
+
+    #[inline]
+    pub fn get_context_info(&self) -> ContextInfo {
+        self.info.clone()
     }
 
     #[inline]
-    pub unsafe fn raw_handle(&self) -> *mut c_void {
-        self.context as *mut _
+    pub fn get_extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    #[inline]
+    pub unsafe fn raw_handle(&self) -> osmesa_sys::OSMesaContext {
+        self.context
     }
 }
 
@@ -207,3 +371,10 @@ impl Drop for OsMesaContext {
 
 unsafe impl Send for OsMesaContext {}
 unsafe impl Sync for OsMesaContext {}
+
+/// Allocates a zeroed buffer big enough for the given dimensions.
+#[inline]
+fn allocate_buffer((width, height): (u32, u32), bytes_per_pixel: usize) -> Vec<u32> {
+    let len = width as usize * height as usize * bytes_per_pixel;
+    vec![0; (len + 3) / 4]
+}
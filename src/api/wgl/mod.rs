@@ -1,7 +1,9 @@
 #![cfg(any(target_os = "windows"))]
 
 use ContextError;
+use ContextInfo;
 use CreationError;
+use Extensions;
 use GlAttributes;
 use GlRequest;
 use GlProfile;
@@ -45,6 +47,15 @@ pub struct Context {
 
     /// The pixel format that has been used to create this context.
     pixel_format: PixelFormat,
+
+    /// The version, profile and flags that the context has been created with.
+    info: ContextInfo,
+
+    /// The WGL extensions that were available when creating the context.
+    extensions: Extensions,
+
+    /// Whether vsync is enabled, `None` if `WGL_EXT_swap_control` isn't available.
+    vsync: Option<bool>,
 }
 
 /// A simple wrapper that destroys the window when it is destroyed.
@@ -107,6 +118,11 @@ impl Context {
             format!("")
         };
 
+        // the configurations of `available_configs` are GLX and EGL ones
+        if pf_reqs.config_id.is_some() {
+            return Err(CreationError::NoAvailablePixelFormat);
+        }
+
         // calling SetPixelFormat
         let pixel_format = {
             let (id, f) = if extensions.split(' ').find(|&i| i == "WGL_ARB_pixel_format")
@@ -123,27 +139,60 @@ impl Context {
             f
         };
 
+        Context::finish(window, hdc, &extra_functions, &extensions, opengl, pixel_format)
+    }
+
+    /// Creates a new context on the window of this context, with the same pixel format, for
+    /// example to replace a context that was lost. `opengl.sharing` is usually `None`, as the
+    /// objects of a lost context are lost as well.
+    ///
+    /// # Unsafety
+    ///
+    /// `window` must be the window of this context, and must continue to exist as long as the
+    /// resulting `Context` exists.
+    pub unsafe fn recreate(&self, opengl: &GlAttributes<HGLRC>, window: HWND)
+                           -> Result<Context, CreationError>
+    {
+        let extra_functions = try!(load_extra_functions(window));
+        let extensions = self.extensions.iter().cloned().collect::<Vec<_>>().join(" ");
+
+        // the pixel format of a window can only be set once
+        Context::finish(window, self.hdc, &extra_functions, &extensions, opengl,
+                        self.pixel_format.clone())
+    }
+
+    /// Creates the OpenGL context on `hdc`, whose pixel format has been set, and handles vsync.
+    unsafe fn finish(window: HWND, hdc: HDC, extra_functions: &gl::wgl_extra::Wgl,
+                     extensions: &str, opengl: &GlAttributes<HGLRC>, pixel_format: PixelFormat)
+                     -> Result<Context, CreationError>
+    {
         // creating the OpenGL context
-        let context = try!(create_context(Some((&extra_functions, pf_reqs, opengl, &extensions)),
-                                          window, hdc));
+        let (context, info) = try!(create_context(Some((extra_functions, opengl, extensions)),
+                                                  window, hdc));
 
         // loading the opengl32 module
         let gl_library = try!(load_opengl32_dll());
 
         // handling vsync
-        if extensions.split(' ').find(|&i| i == "WGL_EXT_swap_control").is_some() {
+        let vsync = if extensions.split(' ').find(|&i| i == "WGL_EXT_swap_control").is_some() {
             let _guard = try!(CurrentContextGuard::make_current(hdc, context.0));
 
             if extra_functions.SwapIntervalEXT(if opengl.vsync { 1 } else { 0 }) == 0 {
                 return Err(CreationError::OsError(format!("wglSwapIntervalEXT failed")));
             }
-        }
+            Some(opengl.vsync)
+        } else {
+            None
+        };
 
         Ok(Context {
             context: context,
             hdc: hdc,
             gl_library: gl_library,
             pixel_format: pixel_format,
+            info: info,
+            extensions: Extensions::from_extension_string(extensions),
+            vsync: vsync,
         })
     }
 
@@ -200,6 +249,21 @@ impl Context {
     pub fn get_pixel_format(&self) -> PixelFormat {
         self.pixel_format.clone()
     }
+
+    #[inline]
+    pub fn get_context_info(&self) -> ContextInfo {
+        self.info.clone()
+    }
+
+    #[inline]
+    pub fn get_extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    #[inline]
+    pub fn get_vsync(&self) -> Option<bool> {
+        self.vsync
+    }
 }
 
 unsafe impl Send for Context {}
@@ -212,14 +276,22 @@ unsafe impl Sync for Context {}
 ///
 /// Otherwise, only the basic API will be used and the chances of `CreationError::NotSupported`
 /// being returned increase.
-unsafe fn create_context(extra: Option<(&gl::wgl_extra::Wgl, &PixelFormatRequirements,
-                                        &GlAttributes<HGLRC>, &str)>,
+unsafe fn create_context(extra: Option<(&gl::wgl_extra::Wgl, &GlAttributes<HGLRC>, &str)>,
                          _: HWND, hdc: HDC)
-                         -> Result<ContextWrapper, CreationError>
+                         -> Result<(ContextWrapper, ContextInfo), CreationError>
 {
     let share;
 
-    if let Some((extra_functions, _pf_reqs, opengl, extensions)) = extra {
+    // updated below with what is actually requested
+    let mut info = ContextInfo {
+        version: (1, 0),
+        profile: None,
+        debug: false,
+        forward_compatible: false,
+        robustness: Robustness::NotRobust,
+    };
+
+    if let Some((extra_functions, opengl, extensions)) = extra {
         share = opengl.sharing.unwrap_or(ptr::null_mut());
 
         if extensions.split(' ').find(|&i| i == "WGL_ARB_create_context").is_some() {
@@ -232,6 +304,7 @@ unsafe fn create_context(extra: Option<(&gl::wgl_extra::Wgl, &PixelFormatRequire
                     attributes.push(major as c_int);
                     attributes.push(gl::wgl_extra::CONTEXT_MINOR_VERSION_ARB as c_int);
                     attributes.push(minor as c_int);
+                    info.version = (major, minor);
                 },
                 GlRequest::Specific(Api::OpenGlEs, (major, minor)) => {
                     if extensions.split(' ').find(|&i| i == "WGL_EXT_create_context_es2_profile")
@@ -247,6 +320,7 @@ unsafe fn create_context(extra: Option<(&gl::wgl_extra::Wgl, &PixelFormatRequire
                     attributes.push(major as c_int);
                     attributes.push(gl::wgl_extra::CONTEXT_MINOR_VERSION_ARB as c_int);
                     attributes.push(minor as c_int);
+                    info.version = (major, minor);
                 },
                 GlRequest::Specific(_, _) => return Err(CreationError::OpenGlVersionNotSupported),
                 GlRequest::GlThenGles { opengl_version: (major, minor), .. } => {
@@ -254,9 +328,19 @@ unsafe fn create_context(extra: Option<(&gl::wgl_extra::Wgl, &PixelFormatRequire
                     attributes.push(major as c_int);
                     attributes.push(gl::wgl_extra::CONTEXT_MINOR_VERSION_ARB as c_int);
                     attributes.push(minor as c_int);
+                    info.version = (major, minor);
                 },
             }
 
+            // profiles only exist since OpenGL 3.2, and the core profile is the default one
+            let is_gles = match opengl.version {
+                GlRequest::Specific(Api::OpenGlEs, _) => true,
+                _ => false,
+            };
+            if !is_gles && info.version >= (3, 2) {
+                info.profile = Some(opengl.profile.unwrap_or(GlProfile::Core));
+            }
+
             if let Some(profile) = opengl.profile {
                 if extensions.split(' ').find(|&i| i == "WGL_ARB_create_context_profile").is_some()
                 {
@@ -283,11 +367,13 @@ unsafe fn create_context(extra: Option<(&gl::wgl_extra::Wgl, &PixelFormatRequire
                             attributes.push(gl::wgl_extra::CONTEXT_RESET_NOTIFICATION_STRATEGY_ARB as c_int);
                             attributes.push(gl::wgl_extra::NO_RESET_NOTIFICATION_ARB as c_int);
                             flags = flags | gl::wgl_extra::CONTEXT_ROBUST_ACCESS_BIT_ARB as c_int;
+                            info.robustness = Robustness::RobustNoResetNotification;
                         },
                         Robustness::RobustLoseContextOnReset | Robustness::TryRobustLoseContextOnReset => {
                             attributes.push(gl::wgl_extra::CONTEXT_RESET_NOTIFICATION_STRATEGY_ARB as c_int);
                             attributes.push(gl::wgl_extra::LOSE_CONTEXT_ON_RESET_ARB as c_int);
                             flags = flags | gl::wgl_extra::CONTEXT_ROBUST_ACCESS_BIT_ARB as c_int;
+                            info.robustness = Robustness::RobustLoseContextOnReset;
                         },
                         Robustness::NotRobust => (),
                         Robustness::NoError => (),
@@ -301,8 +387,14 @@ unsafe fn create_context(extra: Option<(&gl::wgl_extra::Wgl, &PixelFormatRequire
                     }
                 }
 
+                if opengl.forward_compatible {
+                    flags = flags | gl::wgl_extra::CONTEXT_FORWARD_COMPATIBLE_BIT_ARB as c_int;
+                    info.forward_compatible = true;
+                }
+
                 if opengl.debug {
                     flags = flags | gl::wgl_extra::CONTEXT_DEBUG_BIT_ARB as c_int;
+                    info.debug = true;
                 }
 
                 flags
@@ -321,7 +413,7 @@ unsafe fn create_context(extra: Option<(&gl::wgl_extra::Wgl, &PixelFormatRequire
                 return Err(CreationError::OsError(format!("wglCreateContextAttribsARB failed: {}",
                                                       format!("{}", io::Error::last_os_error()))));
             } else {
-                return Ok(ContextWrapper(ctxt as HGLRC));
+                return Ok((ContextWrapper(ctxt as HGLRC), info));
             }
         }
 
@@ -342,7 +434,7 @@ unsafe fn create_context(extra: Option<(&gl::wgl_extra::Wgl, &PixelFormatRequire
         }
     };
 
-    Ok(ContextWrapper(ctxt as HGLRC))
+    Ok((ContextWrapper(ctxt as HGLRC), info))
 }
 
 /// Chooses a pixel formats without using WGL.
@@ -449,13 +541,23 @@ unsafe fn choose_native_pixel_format(hdc: HDC, reqs: &PixelFormatRequirements)
     let pf_desc = PixelFormat {
         hardware_accelerated: (output.dwFlags & PFD_GENERIC_FORMAT) == 0,
         color_bits: output.cRedBits + output.cGreenBits + output.cBlueBits,
+        red_bits: output.cRedBits,
+        green_bits: output.cGreenBits,
+        blue_bits: output.cBlueBits,
         alpha_bits: output.cAlphaBits,
         depth_bits: output.cDepthBits,
         stencil_bits: output.cStencilBits,
+        accum_bits: output.cAccumRedBits,
+        aux_buffers: output.cAuxBuffers,
         stereoscopy: (output.dwFlags & PFD_STEREO) != 0,
         double_buffer: (output.dwFlags & PFD_DOUBLEBUFFER) != 0,
         multisampling: None,
         srgb: false,
+        float_color_buffer: false,
+        transparent: false,
+        release_behavior: ReleaseBehavior::Flush,
+        native_visual_id: None,
+        config_id: None,
     };
 
     if pf_desc.alpha_bits < reqs.alpha_bits.unwrap_or(0) {
@@ -470,6 +572,11 @@ unsafe fn choose_native_pixel_format(hdc: HDC, reqs: &PixelFormatRequirements)
     if pf_desc.color_bits < reqs.color_bits.unwrap_or(0) {
         return Err(());
     }
+    if let Some((red, green, blue)) = reqs.channel_bits {
+        if pf_desc.red_bits < red || pf_desc.green_bits < green || pf_desc.blue_bits < blue {
+            return Err(());
+        }
+    }
     if let Some(req) = reqs.hardware_accelerated {
         if pf_desc.hardware_accelerated != req {
             return Err(());
@@ -520,7 +627,14 @@ unsafe fn choose_arb_pixel_format(extra: &gl::wgl_extra::Wgl, extensions: &str,
             });
         }
 
-        if let Some(color) = reqs.color_bits {
+        if let Some((red, green, blue)) = reqs.channel_bits {
+            out.push(gl::wgl_extra::RED_BITS_ARB as c_int);
+            out.push(red as c_int);
+            out.push(gl::wgl_extra::GREEN_BITS_ARB as c_int);
+            out.push(green as c_int);
+            out.push(gl::wgl_extra::BLUE_BITS_ARB as c_int);
+            out.push(blue as c_int);
+        } else if let Some(color) = reqs.color_bits {
             out.push(gl::wgl_extra::COLOR_BITS_ARB as c_int);
             out.push(color as c_int);
         }
@@ -605,15 +719,22 @@ unsafe fn choose_arb_pixel_format(extra: &gl::wgl_extra::Wgl, extensions: &str,
         value as u32
     };
 
+    let red_bits = get_info(gl::wgl_extra::RED_BITS_ARB) as u8;
+    let green_bits = get_info(gl::wgl_extra::GREEN_BITS_ARB) as u8;
+    let blue_bits = get_info(gl::wgl_extra::BLUE_BITS_ARB) as u8;
+
     let pf_desc = PixelFormat {
         hardware_accelerated: get_info(gl::wgl_extra::ACCELERATION_ARB) !=
                                                                 gl::wgl_extra::NO_ACCELERATION_ARB,
-        color_bits: get_info(gl::wgl_extra::RED_BITS_ARB) as u8 +
-                    get_info(gl::wgl_extra::GREEN_BITS_ARB) as u8 +
-                    get_info(gl::wgl_extra::BLUE_BITS_ARB) as u8,
+        color_bits: red_bits + green_bits + blue_bits,
+        red_bits: red_bits,
+        green_bits: green_bits,
+        blue_bits: blue_bits,
         alpha_bits: get_info(gl::wgl_extra::ALPHA_BITS_ARB) as u8,
         depth_bits: get_info(gl::wgl_extra::DEPTH_BITS_ARB) as u8,
         stencil_bits: get_info(gl::wgl_extra::STENCIL_BITS_ARB) as u8,
+        accum_bits: get_info(gl::wgl_extra::ACCUM_RED_BITS_ARB) as u8,
+        aux_buffers: get_info(gl::wgl_extra::AUX_BUFFERS_ARB) as u8,
         stereoscopy: get_info(gl::wgl_extra::STEREO_ARB) != 0,
         double_buffer: get_info(gl::wgl_extra::DOUBLE_BUFFER_ARB) != 0,
         multisampling: {
@@ -633,6 +754,16 @@ unsafe fn choose_arb_pixel_format(extra: &gl::wgl_extra::Wgl, extensions: &str,
         } else {
             false
         },
+        float_color_buffer: reqs.float_color_buffer,
+        transparent: false,
+        release_behavior: match reqs.release_behavior {
+            ReleaseBehavior::None if extensions.split(' ')
+                                               .find(|&i| i == "WGL_ARB_context_flush_control")
+                                               .is_some() => ReleaseBehavior::None,
+            _ => ReleaseBehavior::Flush,
+        },
+        native_visual_id: None,
+        config_id: None,
     };
 
     Ok((format_id, pf_desc))
@@ -756,7 +887,7 @@ unsafe fn load_extra_functions(window: HWND) -> Result<gl::wgl_extra::Wgl, Creat
     }
 
     // creating the dummy OpenGL context and making it current
-    let dummy_context = try!(create_context(None, dummy_window.0, dummy_window.1));
+    let (dummy_context, _) = try!(create_context(None, dummy_window.0, dummy_window.1));
     let _current_context = try!(CurrentContextGuard::make_current(dummy_window.1,
                                                                   dummy_context.0));
 
@@ -0,0 +1,207 @@
+use CreationError;
+use EventsLoop;
+use PixelFormat;
+use PixelFormatRequirements;
+
+use platform;
+
+use std::fmt;
+use std::sync::Arc;
+
+/// Identifies a framebuffer configuration returned by `available_configs` or
+/// `HeadlessRendererBuilder::available_configs`. Pass it to `GlBuilder::with_config` to build a
+/// context with this configuration.
+///
+/// An id is only meaningful for the backend and the display it was listed on. It can be stored,
+/// for example in a settings file, with `backend` and `raw`, and restored with `new`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ConfigId {
+    pub(crate) backend: ConfigBackend,
+    pub(crate) id: i32,
+}
+
+/// The API that provides a configuration.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ConfigBackend {
+    /// A `GLXFBConfig`.
+    Glx,
+    /// An `EGLConfig`.
+    Egl,
+    /// An `EGLConfig` of the `EGL_MESA_platform_surfaceless` display, whose configurations are
+    /// not the ones of the other displays.
+    EglSurfaceless,
+}
+
+impl ConfigId {
+    /// Creates an id from the values returned by `backend` and `raw`.
+    #[inline]
+    pub fn new(backend: ConfigBackend, raw: i32) -> ConfigId {
+        ConfigId {
+            backend: backend,
+            id: raw,
+        }
+    }
+
+    /// Returns the API that provides the configuration.
+    #[inline]
+    pub fn backend(&self) -> ConfigBackend {
+        self.backend
+    }
+
+    /// Returns the value of `GLX_FBCONFIG_ID` or `EGL_CONFIG_ID` of the configuration.
+    #[inline]
+    pub fn raw(&self) -> i32 {
+        self.id
+    }
+}
+
+/// Describes a framebuffer configuration offered by the system.
+#[derive(Debug, Clone)]
+pub struct ConfigDescriptor {
+    /// The id to pass to `GlBuilder::with_config`.
+    pub id: ConfigId,
+
+    /// The format of the framebuffers created with this configuration.
+    pub pixel_format: PixelFormat,
+
+    /// True if the color buffer is in a floating-point format.
+    pub float_color_buffer: bool,
+
+    /// True if the configuration can be used for a window.
+    pub window: bool,
+
+    /// True if the configuration can be used for an offscreen pbuffer.
+    pub pbuffer: bool,
+}
+
+/// Lists the framebuffer configurations that can be used to build a `GlWindow` on this events
+/// loop.
+///
+/// On X11, both the GLX and the EGL configurations are returned, if the libraries are available.
+/// Only X11 and Wayland are currently supported, other platforms return
+/// `CreationError::NotSupported`.
+#[inline]
+pub fn available_configs(events_loop: &EventsLoop)
+                         -> Result<Vec<ConfigDescriptor>, CreationError>
+{
+    platform::available_configs(events_loop)
+}
+
+/// Ranks the configurations that satisfy the pixel format requirements. See
+/// `GlBuilder::with_config_ranking`.
+#[derive(Clone)]
+pub struct ConfigRanking(Arc<Fn(&ConfigDescriptor) -> Option<u32> + Send + Sync>);
+
+impl ConfigRanking {
+    /// Wraps a closure that returns the score of a configuration, or `None` to reject it.
+    #[inline]
+    pub fn new<F>(rank: F) -> ConfigRanking
+        where F: Fn(&ConfigDescriptor) -> Option<u32> + Send + Sync + 'static
+    {
+        ConfigRanking(Arc::new(rank))
+    }
+
+    /// Returns the score of `config`.
+    #[inline]
+    pub fn rank(&self, config: &ConfigDescriptor) -> Option<u32> {
+        (self.0)(config)
+    }
+}
+
+impl fmt::Debug for ConfigRanking {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("ConfigRanking")
+    }
+}
+
+/// Chooses the configuration that best matches `reqs` among `candidates`, and returns its index.
+///
+/// This is how the GLX and EGL backends choose between the configurations offered by the
+/// system. The candidates that don't provide the requested number of bits, buffers, samples,
+/// hardware acceleration, double buffering, stereoscopy, sRGB or floating-point color buffer are
+/// ignored. If `reqs.ranking` is set, the candidate with the highest score is chosen. Otherwise,
+/// the closest candidate is chosen, by order of importance:
+///
+/// - Hardware acceleration, if `reqs.hardware_accelerated` is `None`.
+/// - The number of samples, which is zero if `reqs.multisampling` is `None`.
+/// - The number of color and alpha bits exceeding the requested ones, per channel if
+///   `reqs.channel_bits` is set.
+/// - The number of depth and stencil bits exceeding the requested ones.
+///
+/// The first candidate wins a tie, so the order of the system is kept for equivalent
+/// configurations. Returns `None` if no candidate is suitable.
+pub fn select_config(candidates: &[ConfigDescriptor], reqs: &PixelFormatRequirements)
+                     -> Option<usize>
+{
+    let suitable = candidates.iter().enumerate().filter(|&(_, config)| satisfies(config, reqs));
+
+    match reqs.ranking {
+        Some(ref ranking) => {
+            let mut best: Option<(usize, u32)> = None;
+            for (index, config) in suitable {
+                match (ranking.rank(config), best) {
+                    (Some(score), Some((_, best_score))) if score <= best_score => (),
+                    (Some(score), _) => best = Some((index, score)),
+                    (None, _) => (),
+                }
+            }
+            best.map(|(index, _)| index)
+        },
+        None => {
+            // `min_by_key` returns the first minimum
+            suitable.min_by_key(|&(_, config)| distance(&config.pixel_format, reqs))
+                    .map(|(index, _)| index)
+        },
+    }
+}
+
+/// Returns true if `config` provides at least what `reqs` asks for.
+fn satisfies(config: &ConfigDescriptor, reqs: &PixelFormatRequirements) -> bool {
+    let format = &config.pixel_format;
+    let enough = |requested: Option<u8>, got: u8| requested.map_or(true, |bits| got >= bits);
+    let samples = format.multisampling.unwrap_or(0);
+
+    let color = match reqs.channel_bits {
+        Some((red, green, blue)) => format.red_bits >= red && format.green_bits >= green &&
+                                    format.blue_bits >= blue,
+        None => enough(reqs.color_bits, format.color_bits),
+    };
+
+    let matches = |requested: Option<bool>, got: bool| requested.map_or(true, |value| got == value);
+
+    config.float_color_buffer == reqs.float_color_buffer && color &&
+        matches(reqs.hardware_accelerated, format.hardware_accelerated) &&
+        matches(reqs.double_buffer, format.double_buffer) &&
+        (!reqs.stereoscopy || format.stereoscopy) &&
+        (!reqs.srgb || format.srgb) &&
+        enough(reqs.alpha_bits, format.alpha_bits) &&
+        enough(reqs.depth_bits, format.depth_bits) &&
+        enough(reqs.stencil_bits, format.stencil_bits) &&
+        enough(reqs.accum_bits, format.accum_bits) &&
+        enough(reqs.aux_buffers, format.aux_buffers) &&
+        match reqs.multisampling {
+            Some(0) => samples == 0,
+            Some(requested) => samples >= requested,
+            None => true,
+        }
+}
+
+/// Returns how far `format` is from `reqs`, to be compared lexicographically.
+fn distance(format: &PixelFormat, reqs: &PixelFormatRequirements) -> (bool, u16, u32, u32) {
+    let excess = |requested: Option<u8>, got: u8| {
+        requested.map_or(0, |bits| got.saturating_sub(bits) as u32)
+    };
+
+    let acceleration = format.hardware_accelerated != reqs.hardware_accelerated.unwrap_or(true);
+    let samples = format.multisampling.unwrap_or(0).saturating_sub(reqs.multisampling.unwrap_or(0));
+    let color = match reqs.channel_bits {
+        Some((red, green, blue)) => excess(Some(red), format.red_bits) +
+                                    excess(Some(green), format.green_bits) +
+                                    excess(Some(blue), format.blue_bits),
+        None => excess(reqs.color_bits, format.color_bits),
+    } + excess(reqs.alpha_bits, format.alpha_bits);
+    let depth_stencil = excess(reqs.depth_bits, format.depth_bits) +
+                        excess(reqs.stencil_bits, format.stencil_bits);
+
+    (acceleration, samples, color, depth_stencil)
+}
@@ -0,0 +1,107 @@
+use ContextError;
+use GlContext;
+
+use api::gl;
+
+use std::collections::HashSet;
+use std::ffi::CStr;
+use std::slice;
+
+/// List of the extensions supported by the window system or by an OpenGL context.
+///
+/// The names are kept in the order they were reported, and are also stored in a set so that
+/// `has_extension` doesn't go through the whole list.
+#[derive(Debug, Clone, Default)]
+pub struct Extensions {
+    names: Vec<String>,
+    set: HashSet<String>,
+}
+
+impl Extensions {
+    /// Builds the list from extension names. Duplicates and empty names are ignored.
+    pub(crate) fn new<I>(names: I) -> Extensions where I: IntoIterator<Item = String> {
+        let mut extensions = Extensions::default();
+        for name in names {
+            if !name.is_empty() && extensions.set.insert(name.clone()) {
+                extensions.names.push(name);
+            }
+        }
+        extensions
+    }
+
+    /// Builds the list from a space-separated list of extensions, as returned by
+    /// `glXQueryExtensionsString`, `eglQueryString` or `wglGetExtensionsStringARB`.
+    #[inline]
+    pub(crate) fn from_extension_string(list: &str) -> Extensions {
+        Extensions::new(list.split(' ').map(|name| name.to_owned()))
+    }
+
+    /// Returns true if `name` is in the list.
+    #[inline]
+    pub fn has_extension(&self, name: &str) -> bool {
+        self.set.contains(name)
+    }
+
+    /// Returns an iterator over the names of the extensions.
+    #[inline]
+    pub fn iter<'a>(&'a self) -> slice::Iter<'a, String> {
+        self.names.iter()
+    }
+
+    /// Returns the number of extensions.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Returns true if there is no extension.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+impl<'a> IntoIterator for &'a Extensions {
+    type Item = &'a String;
+    type IntoIter = slice::Iter<'a, String>;
+
+    #[inline]
+    fn into_iter(self) -> slice::Iter<'a, String> {
+        self.iter()
+    }
+}
+
+/// Queries the OpenGL extensions of `context`, which must be current.
+pub(crate) fn gl_extensions<C: ?Sized + GlContext>(context: &C)
+                                                   -> Result<Extensions, ContextError>
+{
+    if !context.is_current() {
+        return Err(ContextError::NotCurrent);
+    }
+
+    let gl = gl::Gl::load_with(|symbol| context.get_proc_address(symbol) as *const _);
+    unsafe {
+        // `glGetString(GL_EXTENSIONS)` was removed from core profiles, and `glGetStringi` only
+        // exists since OpenGL 3.0 and OpenGL ES 3.0. Its address can't tell: GLX and EGL return
+        // an address for every function, supported or not.
+        let (_, version) = gl::get_version(&gl);
+        if version >= (3, 0) {
+            let count = gl::get_integer(&gl, gl::NUM_EXTENSIONS);
+            Ok(Extensions::new((0 .. count as u32).filter_map(|index| {
+                let name = gl.GetStringi(gl::EXTENSIONS, index);
+                if name.is_null() {
+                    None
+                } else {
+                    Some(CStr::from_ptr(name as *const _).to_string_lossy().into_owned())
+                }
+            })))
+        } else {
+            let list = gl.GetString(gl::EXTENSIONS);
+            if list.is_null() {
+                return Ok(Extensions::default());
+            }
+            let list = CStr::from_ptr(list as *const _).to_string_lossy();
+            Ok(Extensions::from_extension_string(&list))
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use api::gl;
+use api::gl::types::{GLenum, GLsizei, GLuint};
+
+/// Format of the pixels of an `Image`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ImageFormat {
+    /// Four `u8` per pixel, in the red, green, blue, alpha order.
+    Rgba8,
+    /// Four `u8` per pixel, in the blue, green, red, alpha order.
+    Bgra8,
+    /// Three `u8` per pixel, in the red, green, blue order. The alpha channel is dropped.
+    Rgb8,
+    /// Four `f32` per pixel in the native endianness, in the red, green, blue, alpha order.
+    ///
+    /// On OpenGL ES, this is only available if the color buffer has a floating-point format.
+    RgbaF32,
+}
+
+impl ImageFormat {
+    /// Returns the number of bytes used by one pixel.
+    #[inline]
+    pub fn bytes_per_pixel(&self) -> usize {
+        match *self {
+            ImageFormat::Rgba8 | ImageFormat::Bgra8 => 4,
+            ImageFormat::Rgb8 => 3,
+            ImageFormat::RgbaF32 => 16,
+        }
+    }
+}
+
+/// Order of the rows of an `Image`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RowOrder {
+    /// The first row is the top of the image. This is what most image formats and libraries
+    /// expect.
+    TopDown,
+    /// The first row is the bottom of the image. This is what OpenGL uses.
+    BottomUp,
+}
+
+/// Image read back from the framebuffer of a context.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    /// Width of the image, in pixels.
+    pub width: u32,
+    /// Height of the image, in pixels.
+    pub height: u32,
+    /// Number of bytes between the start of two consecutive rows. Rows are tightly packed, so
+    /// this is always `width * format.bytes_per_pixel()`.
+    pub stride: usize,
+    /// Format of the pixels.
+    pub format: ImageFormat,
+    /// Order of the rows in `data`.
+    pub row_order: RowOrder,
+    /// The pixels, row by row.
+    pub data: Vec<u8>,
+}
+
+impl Image {
+    /// Returns the bytes of the given row, `0` being the first row of `data`.
+    #[inline]
+    pub fn row(&self, y: u32) -> &[u8] {
+        let start = y as usize * self.stride;
+        &self.data[start .. start + self.stride]
+    }
+}
+
+/// Reads the content of `framebuffer` with `glReadPixels`.
+///
+/// The context must be current. The pixel pack state and the framebuffer bindings are restored
+/// before returning.
+pub unsafe fn read_pixels(gl: &gl::Gl, framebuffer: GLuint, (width, height): (u32, u32),
+                          format: ImageFormat, row_order: RowOrder) -> Image
+{
+    // separate read and draw framebuffers and pixel buffer objects only exist since GL 3.0
+    // and GLES 3.0
+    let (_, version) = gl::get_version(gl);
+    let modern = version >= (3, 0);
+
+    let binding_target = if modern { gl::READ_FRAMEBUFFER } else { gl::FRAMEBUFFER };
+    let binding_query = if modern { gl::READ_FRAMEBUFFER_BINDING } else { gl::FRAMEBUFFER_BINDING };
+    let previous_framebuffer = gl::get_integer(gl, binding_query);
+    let previous_alignment = gl::get_integer(gl, gl::PACK_ALIGNMENT);
+    let previous_pack_buffer = if modern {
+        gl::get_integer(gl, gl::PIXEL_PACK_BUFFER_BINDING)
+    } else {
+        0
+    };
+
+    gl.BindFramebuffer(binding_target, framebuffer);
+    gl.PixelStorei(gl::PACK_ALIGNMENT, 1);
+    if previous_pack_buffer != 0 {
+        gl.BindBuffer(gl::PIXEL_PACK_BUFFER, 0);
+    }
+
+    // 8-bit formats are always read as RGBA, which is the only combination guaranteed to be
+    // supported by OpenGL ES, then converted
+    let (gl_type, read_bpp): (GLenum, usize) = match format {
+        ImageFormat::RgbaF32 => (gl::FLOAT, 16),
+        _ => (gl::UNSIGNED_BYTE, 4),
+    };
+
+    let mut raw = vec![0u8; width as usize * height as usize * read_bpp];
+    gl.ReadPixels(0, 0, width as GLsizei, height as GLsizei, gl::RGBA, gl_type,
+                  raw.as_mut_ptr() as *mut _);
+
+    if previous_pack_buffer != 0 {
+        gl.BindBuffer(gl::PIXEL_PACK_BUFFER, previous_pack_buffer as GLuint);
+    }
+    gl.PixelStorei(gl::PACK_ALIGNMENT, previous_alignment);
+    gl.BindFramebuffer(binding_target, previous_framebuffer as GLuint);
+
+    let data = match format {
+        ImageFormat::Rgba8 | ImageFormat::RgbaF32 => raw,
+        ImageFormat::Bgra8 => {
+            for pixel in raw.chunks_mut(4) {
+                pixel.swap(0, 2);
+            }
+            raw
+        },
+        ImageFormat::Rgb8 => raw.chunks(4).flat_map(|pixel| pixel[.. 3].iter().cloned()).collect(),
+    };
+
+    let stride = width as usize * format.bytes_per_pixel();
+    let data = match row_order {
+        RowOrder::BottomUp => data,
+        RowOrder::TopDown if stride == 0 => data,
+        RowOrder::TopDown => {
+            data.chunks(stride).rev().flat_map(|row| row.iter().cloned()).collect()
+        },
+    };
+
+    Image {
+        width: width,
+        height: height,
+        stride: stride,
+        format: format,
+        row_order: row_order,
+        data: data,
+    }
+}
@@ -1,7 +1,11 @@
 use Api;
+use ConfigDescriptor;
 use ContextError;
+use ContextInfo;
 use CreationError;
+use Extensions;
 use GlAttributes;
+use GlBuilder;
 use GlContext;
 use GlProfile;
 use GlRequest;
@@ -9,7 +13,13 @@ use PixelFormat;
 use PixelFormatRequirements;
 use Robustness;
 
+use api::gl;
+use framebuffer::{self, Image, ImageFormat, RowOrder};
 use platform;
+use share_group::ShareGroup;
+use strict;
+
+use std::sync::{Arc, Mutex};
 
 /// Object that allows you to build headless contexts.
 #[derive(Clone)]
@@ -18,13 +28,15 @@ pub struct HeadlessRendererBuilder<'a> {
     pub dimensions: (u32, u32),
 
     /// The OpenGL attributes to build the context with.
-    pub opengl: GlAttributes<&'a platform::HeadlessContext>,
+    pub opengl: GlAttributes<&'a HeadlessContext>,
 
-    // Should be made public once it's stabilized.
-    pf_reqs: PixelFormatRequirements,
+    /// The requirements of the pixel format of the context.
+    pub pf_reqs: PixelFormatRequirements,
 
     /// Platform-specific configuration.
-    platform_specific: platform::PlatformSpecificHeadlessBuilderAttributes,
+    pub(crate) platform_specific: platform::PlatformSpecificHeadlessBuilderAttributes,
+
+    share_group: Option<ShareGroup>,
 }
 
 impl<'a> HeadlessRendererBuilder<'a> {
@@ -39,64 +51,183 @@ impl<'a> HeadlessRendererBuilder<'a> {
             },
             opengl: Default::default(),
             platform_specific: Default::default(),
+            share_group: None,
         }
     }
 
-    /// Sets how the backend should choose the OpenGL API and version.
+    /// Lists the framebuffer configurations that can be used to build a headless context with
+    /// this builder. Pass the id of one of them to `GlBuilder::with_config` to use it.
+    ///
+    /// On Linux, the configurations of every EGL and GLX backend that the builder would try are
+    /// returned. Other platforms return `CreationError::NotSupported`.
     #[inline]
-    pub fn with_gl(mut self, request: GlRequest) -> HeadlessRendererBuilder<'a> {
-        self.opengl.version = request;
-        self
+    pub fn available_configs(&self) -> Result<Vec<ConfigDescriptor>, CreationError> {
+        platform::HeadlessContext::available_configs(&self.platform_specific)
     }
 
-    /// Sets the desired OpenGL context profile.
+    /// Builds the headless context.
+    ///
+    /// Error should be very rare and only occur in case of permission denied, incompatible system,
+    ///  out of memory, etc.
     #[inline]
-    pub fn with_gl_profile(mut self, profile: GlProfile) -> HeadlessRendererBuilder<'a> {
-        self.opengl.profile = Some(profile);
-        self
+    pub fn build(self) -> Result<HeadlessContext, CreationError> {
+        let HeadlessRendererBuilder { dimensions, opengl, pf_reqs, platform_specific,
+                                      share_group } = self;
+        pf_reqs.validate()?;
+        let opengl = opengl.map_sharing(|ctxt| &*ctxt.context);
+        if opengl.sharing.is_some() && share_group.is_some() {
+            let msg = "Cannot use both `with_shared_lists` and `with_share_group`";
+            return Err(CreationError::PlatformSpecific(msg.into()));
+        }
+
+        let create = |shared: Option<&platform::HeadlessContext>| {
+            let mut opengl = opengl.clone();
+            if shared.is_some() {
+                opengl.sharing = shared;
+            }
+            platform::HeadlessContext::new(dimensions, &pf_reqs, &opengl, &platform_specific)
+                .map(|context| (Arc::new(context), ()))
+        };
+        let (context, ()) = match share_group {
+            Some(ref group) => group.build(create)?,
+            None => create(None)?,
+        };
+
+        Ok(HeadlessContext {
+            context: context,
+            dimensions: Mutex::new(dimensions),
+            share_group: share_group,
+        })
     }
 
-    /// Sets the *debug* flag for the OpenGL context.
+    /// Builds the headless context.
     ///
-    /// The default value for this flag is `cfg!(ndebug)`, which means that it's enabled
-    /// when you run `cargo build` and disabled when you run `cargo build --release`.
+    /// The context is build in a *strict* way. That means that if the backend couldn't give
+    /// you what you requested, an `Err` containing every unmet requirement will be returned.
+    ///
+    /// The pixel format and the OpenGL version, profile and robustness are verified. This makes
+    /// the context current.
+    pub fn build_strict(self) -> Result<HeadlessContext, CreationError> {
+        let pf_reqs = self.pf_reqs.clone();
+        let opengl = self.opengl.clone();
+        let context = self.build()?;
+        strict::verify(&context, &pf_reqs, &opengl, None)?;
+        Ok(context)
+    }
+
+    // The methods below were inherent before `GlBuilder` existed. They forward to the trait so
+    // that code that doesn't import it keeps building.
+
+    /// See `GlBuilder::with_gl`.
     #[inline]
-    pub fn with_gl_debug_flag(mut self, flag: bool) -> HeadlessRendererBuilder<'a> {
-        self.opengl.debug = flag;
-        self
+    pub fn with_gl(self, request: GlRequest) -> Self {
+        GlBuilder::with_gl(self, request)
     }
 
-    /// Sets the robustness of the OpenGL context. See the docs of `Robustness`.
+    /// See `GlBuilder::with_gl_profile`.
     #[inline]
-    pub fn with_gl_robustness(mut self, robustness: Robustness) -> HeadlessRendererBuilder<'a> {
-        self.opengl.robustness = robustness;
-        self
+    pub fn with_gl_profile(self, profile: GlProfile) -> Self {
+        GlBuilder::with_gl_profile(self, profile)
     }
 
-    /// Builds the headless context.
-    ///
-    /// Error should be very rare and only occur in case of permission denied, incompatible system,
-    ///  out of memory, etc.
+    /// See `GlBuilder::with_gl_debug_flag`.
     #[inline]
-    pub fn build(self) -> Result<HeadlessContext, CreationError> {
-        platform::HeadlessContext::new(self.dimensions, &self.pf_reqs, &self.opengl,
-                                       &self.platform_specific)
-                .map(|w| HeadlessContext { context: w })
+    pub fn with_gl_debug_flag(self, flag: bool) -> Self {
+        GlBuilder::with_gl_debug_flag(self, flag)
     }
 
-    /// Builds the headless context.
-    ///
-    /// The context is build in a *strict* way. That means that if the backend couldn't give
-    /// you what you requested, an `Err` will be returned.
+    /// See `GlBuilder::with_gl_robustness`.
     #[inline]
-    pub fn build_strict(self) -> Result<HeadlessContext, CreationError> {
-        self.build()
+    pub fn with_gl_robustness(self, robustness: Robustness) -> Self {
+        GlBuilder::with_gl_robustness(self, robustness)
+    }
+}
+
+impl<'a> GlBuilder<'a> for HeadlessRendererBuilder<'a> {
+    type SharedContext = HeadlessContext;
+
+    #[inline]
+    fn gl_attributes_mut(&mut self) -> &mut GlAttributes<&'a HeadlessContext> {
+        &mut self.opengl
+    }
+
+    #[inline]
+    fn pixel_format_requirements_mut(&mut self) -> &mut PixelFormatRequirements {
+        &mut self.pf_reqs
+    }
+
+    #[inline]
+    fn share_group_mut(&mut self) -> &mut Option<ShareGroup> {
+        &mut self.share_group
     }
 }
 
 /// Represents a headless OpenGL context.
 pub struct HeadlessContext {
-    pub(crate) context: platform::HeadlessContext,
+    // dropped before `share_group`, which may hold the native context this one shares with
+    pub(crate) context: Arc<platform::HeadlessContext>,
+    // not a `Cell` so that `HeadlessContext` stays `Sync`
+    dimensions: Mutex<(u32, u32)>,
+    share_group: Option<ShareGroup>,
+}
+
+impl HeadlessContext {
+    pub(crate) fn from_platform(context: platform::HeadlessContext, dimensions: (u32, u32))
+                                -> HeadlessContext
+    {
+        HeadlessContext {
+            context: Arc::new(context),
+            dimensions: Mutex::new(dimensions),
+            share_group: None,
+        }
+    }
+
+    /// Returns the share group the context was built into, if any.
+    #[inline]
+    pub fn share_group(&self) -> Option<&ShareGroup> {
+        self.share_group.as_ref()
+    }
+
+    /// Returns the dimensions of the framebuffer, in pixels.
+    #[inline]
+    pub fn get_dimensions(&self) -> (u32, u32) {
+        *self.dimensions.lock().unwrap()
+    }
+
+    /// Resizes the framebuffer of the context. The OpenGL objects are kept, but the content of
+    /// the framebuffer is lost.
+    ///
+    /// If the context is current, it stays current and renders to the resized framebuffer. On
+    /// error, for example if the dimensions are above the maximum size of a pbuffer, the
+    /// framebuffer keeps its previous dimensions and content.
+    pub fn try_resize(&self, width: u32, height: u32) -> Result<(), CreationError> {
+        let mut dimensions = self.dimensions.lock().unwrap();
+        self.context.resize(width, height)?;
+        *dimensions = (width, height);
+        Ok(())
+    }
+
+    /// Reads the whole content of the framebuffer of the context into memory.
+    ///
+    /// The image is the one that was last rendered. If the context is multisampled, the samples
+    /// are resolved first. The framebuffer bindings and the pixel pack state of the context are
+    /// left untouched.
+    ///
+    /// Returns `ContextError::NotCurrent` if the context is not current.
+    pub fn read_pixels(&self, format: ImageFormat, row_order: RowOrder)
+                       -> Result<Image, ContextError>
+    {
+        if !self.is_current() {
+            return Err(ContextError::NotCurrent);
+        }
+
+        let gl = gl::Gl::load_with(|symbol| self.get_proc_address(symbol) as *const _);
+        unsafe {
+            let framebuffer = self.context.resolve_framebuffer();
+            Ok(framebuffer::read_pixels(&gl, framebuffer, self.get_dimensions(), format,
+                                        row_order))
+        }
+    }
 }
 
 impl GlContext for HeadlessContext {
@@ -140,8 +271,19 @@ impl GlContext for HeadlessContext {
     }
 
     #[inline]
-    fn resize(&self, _width: u32, _height: u32) {
-        // This method does not mean anything for a HeadlessContext.
-        unimplemented!()
+    fn get_context_info(&self) -> ContextInfo {
+        self.context.get_context_info()
+    }
+
+    #[inline]
+    fn get_platform_extensions(&self) -> &Extensions {
+        self.context.get_platform_extensions()
+    }
+
+    /// Resizes the framebuffer of the context, see `try_resize`. Errors are ignored, and the
+    /// framebuffer keeps its previous dimensions.
+    #[inline]
+    fn resize(&self, width: u32, height: u32) {
+        let _ = self.try_resize(width, height);
     }
 }
@@ -30,8 +30,13 @@
 //!     the `HeadlessRendererBuilder` object.
 //!
 //! By default only `window` is enabled.
+//!
+//! The `snapshot` feature enables the `snapshot` module, which saves what was rendered as PNG,
+//! PPM or PAM. The `testing` feature enables the `testing` module, a harness for golden-image
+//! tests on top of headless contexts.
 
-#[cfg(target_os = "windows")]
+#[cfg(any(target_os = "windows", target_os = "linux", target_os = "android",
+          target_os = "dragonfly", target_os = "freebsd", target_os = "openbsd"))]
 #[macro_use]
 extern crate lazy_static;
 
@@ -61,7 +66,13 @@ extern crate x11_dl;
 #[cfg(any(target_os = "linux", target_os = "freebsd", target_os = "dragonfly", target_os = "openbsd"))]
 extern crate wayland_client;
 
+pub use config::{ConfigBackend, ConfigDescriptor, ConfigId, ConfigRanking};
+pub use config::{available_configs, select_config};
+pub use extensions::Extensions;
+pub use framebuffer::{Image, ImageFormat, RowOrder};
 pub use headless::{HeadlessRendererBuilder, HeadlessContext};
+pub use reset::ResetStatus;
+pub use share_group::ShareGroup;
 pub use winit::{AvailableMonitorsIter, AxisId, ButtonId, ControlFlow,
                 CreationError as WindowCreationError, CursorState, DeviceEvent, DeviceId,
                 ElementState, Event, EventsLoop, EventsLoopClosed, EventsLoopProxy,
@@ -71,10 +82,22 @@ pub use winit::{AvailableMonitorsIter, AxisId, ButtonId, ControlFlow,
                 WindowEvent, WindowId};
 
 use std::io;
+use std::sync::Arc;
 
 mod api;
+mod config;
+mod extensions;
+mod framebuffer;
 mod platform;
 mod headless;
+mod reset;
+mod share_group;
+mod strict;
+
+#[cfg(feature = "snapshot")]
+pub mod snapshot;
+#[cfg(feature = "testing")]
+pub mod testing;
 
 pub mod os;
 
@@ -105,6 +128,41 @@ pub trait GlContext {
     /// Returns the pixel format of the main framebuffer of the context.
     fn get_pixel_format(&self) -> PixelFormat;
 
+    /// Returns the version, profile and flags that the context was created with.
+    fn get_context_info(&self) -> ContextInfo;
+
+    /// Returns the extensions of the window system (GLX, EGL or WGL) that the context was
+    /// created with. With EGL, this includes the client extensions, which don't depend on the
+    /// display.
+    ///
+    /// The list is empty on the platforms that don't have window system extensions.
+    fn get_platform_extensions(&self) -> &Extensions;
+
+    /// Returns the OpenGL or OpenGL ES extensions supported by the context, queried with the
+    /// `glGetStringi` function of the context, or `glGetString` before version 3.0.
+    ///
+    /// Returns `ContextError::NotCurrent` if the context is not current.
+    fn get_gl_extensions(&self) -> Result<Extensions, ContextError> {
+        extensions::gl_extensions(self)
+    }
+
+    /// Returns whether the context was reset since the last call, with the
+    /// `glGetGraphicsResetStatus` function of the context.
+    ///
+    /// Only contexts created with `Robustness::RobustLoseContextOnReset` are notified of resets,
+    /// the others always return `ResetStatus::NoError`. A context that was reset must be
+    /// recreated, see `GlWindow::recreate_context`. The function is the core one since OpenGL 4.5
+    /// and OpenGL ES 3.2, and the one of `GL_KHR_robustness`, `GL_ARB_robustness` or
+    /// `GL_EXT_robustness` before.
+    ///
+    /// On GLX, `swap_buffers` and `make_current` query the reset status of these contexts to
+    /// return `ContextError::ContextLost`, and this may then return `ResetStatus::NoError`.
+    ///
+    /// Returns `ContextError::NotCurrent` if the context is not current.
+    fn get_reset_status(&self) -> Result<ResetStatus, ContextError> {
+        reset::get_reset_status(self)
+    }
+
     /// Resize the GL context.
     ///
     /// Some platforms (macos, wayland) require being manually updated when their window or
@@ -137,15 +195,21 @@ pub trait GlContext {
 /// # }
 /// ```
 pub struct Context {
-    context: platform::Context,
+    // dropped before `share_group`, which may hold the native context this one shares with
+    context: Arc<platform::Context>,
+    share_group: Option<ShareGroup>,
+    // used to create worker contexts
+    gl_attr: GlAttributes<()>,
 }
 
 /// Object that allows you to build `Context`s.
 pub struct ContextBuilder<'a> {
     /// The attributes to use to create the context.
     pub gl_attr: GlAttributes<&'a Context>,
-    // Should be made public once it's stabilized.
-    pf_reqs: PixelFormatRequirements,
+    /// The requirements of the pixel format of the context.
+    pub pf_reqs: PixelFormatRequirements,
+    strict: bool,
+    share_group: Option<ShareGroup>,
 }
 
 /// Represents an OpenGL context and a Window with which it is associated.
@@ -189,20 +253,169 @@ impl<'a> ContextBuilder<'a> {
         ContextBuilder {
             pf_reqs: std::default::Default::default(),
             gl_attr: std::default::Default::default(),
+            strict: false,
+            share_group: None,
         }
     }
 
+    /// Sets whether the context is built in a *strict* way. That means that if the backend
+    /// couldn't give you what you requested, an `Err` containing every unmet requirement will be
+    /// returned.
+    ///
+    /// The pixel format, the OpenGL version, profile and robustness, and vsync when the backend
+    /// can report it, are verified. This makes the context current.
+    ///
+    /// The default value is `false`.
+    #[inline]
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    // The methods below were inherent before `GlBuilder` existed. They forward to the trait so
+    // that code that doesn't import it keeps building.
+
+    /// See `GlBuilder::with_gl`.
+    #[inline]
+    pub fn with_gl(self, request: GlRequest) -> Self {
+        GlBuilder::with_gl(self, request)
+    }
+
+    /// See `GlBuilder::with_gl_profile`.
+    #[inline]
+    pub fn with_gl_profile(self, profile: GlProfile) -> Self {
+        GlBuilder::with_gl_profile(self, profile)
+    }
+
+    /// See `GlBuilder::with_gl_debug_flag`.
+    #[inline]
+    pub fn with_gl_debug_flag(self, flag: bool) -> Self {
+        GlBuilder::with_gl_debug_flag(self, flag)
+    }
+
+    /// See `GlBuilder::with_gl_robustness`.
+    #[inline]
+    pub fn with_gl_robustness(self, robustness: Robustness) -> Self {
+        GlBuilder::with_gl_robustness(self, robustness)
+    }
+
+    /// See `GlBuilder::with_vsync`.
+    #[inline]
+    pub fn with_vsync(self, vsync: bool) -> Self {
+        GlBuilder::with_vsync(self, vsync)
+    }
+
+    /// See `GlBuilder::with_shared_lists`.
+    #[inline]
+    pub fn with_shared_lists(self, other: &'a Context) -> Self {
+        GlBuilder::with_shared_lists(self, other)
+    }
+
+    /// See `GlBuilder::with_multisampling`.
+    #[inline]
+    pub fn with_multisampling(self, samples: u16) -> Self {
+        GlBuilder::with_multisampling(self, samples)
+    }
+
+    /// See `GlBuilder::with_depth_buffer`.
+    #[inline]
+    pub fn with_depth_buffer(self, bits: u8) -> Self {
+        GlBuilder::with_depth_buffer(self, bits)
+    }
+
+    /// See `GlBuilder::with_stencil_buffer`.
+    #[inline]
+    pub fn with_stencil_buffer(self, bits: u8) -> Self {
+        GlBuilder::with_stencil_buffer(self, bits)
+    }
+
+    /// See `GlBuilder::with_pixel_format`.
+    #[inline]
+    pub fn with_pixel_format(self, color_bits: u8, alpha_bits: u8) -> Self {
+        GlBuilder::with_pixel_format(self, color_bits, alpha_bits)
+    }
+
+    /// See `GlBuilder::with_stereoscopy`.
+    #[inline]
+    pub fn with_stereoscopy(self) -> Self {
+        GlBuilder::with_stereoscopy(self)
+    }
+
+    /// See `GlBuilder::with_srgb`.
+    #[inline]
+    pub fn with_srgb(self, srgb_enabled: bool) -> Self {
+        GlBuilder::with_srgb(self, srgb_enabled)
+    }
+}
+
+impl<'a> GlBuilder<'a> for ContextBuilder<'a> {
+    type SharedContext = Context;
+
+    #[inline]
+    fn gl_attributes_mut(&mut self) -> &mut GlAttributes<&'a Context> {
+        &mut self.gl_attr
+    }
+
+    #[inline]
+    fn pixel_format_requirements_mut(&mut self) -> &mut PixelFormatRequirements {
+        &mut self.pf_reqs
+    }
+
+    #[inline]
+    fn share_group_mut(&mut self) -> &mut Option<ShareGroup> {
+        &mut self.share_group
+    }
+}
+
+/// Configuration of the OpenGL context, shared by `ContextBuilder` and
+/// `HeadlessRendererBuilder`.
+///
+/// # Example
+///
+/// ```no_run
+/// # extern crate glutin;
+/// use glutin::GlBuilder;
+/// # fn main() {
+/// let context = glutin::ContextBuilder::new()
+///     .with_depth_buffer(24)
+///     .with_multisampling(4);
+/// let headless = glutin::HeadlessRendererBuilder::new(256, 256)
+///     .with_depth_buffer(24)
+///     .with_multisampling(4);
+/// # }
+/// ```
+pub trait GlBuilder<'a>: Sized {
+    /// The type of the contexts that the built context can share its display lists with.
+    type SharedContext: 'a;
+
+    #[doc(hidden)]
+    fn gl_attributes_mut(&mut self) -> &mut GlAttributes<&'a Self::SharedContext>;
+
+    #[doc(hidden)]
+    fn pixel_format_requirements_mut(&mut self) -> &mut PixelFormatRequirements;
+
+    #[doc(hidden)]
+    fn share_group_mut(&mut self) -> &mut Option<ShareGroup>;
+
     /// Sets how the backend should choose the OpenGL API and version.
     #[inline]
-    pub fn with_gl(mut self, request: GlRequest) -> Self {
-        self.gl_attr.version = request;
+    fn with_gl(mut self, request: GlRequest) -> Self {
+        self.gl_attributes_mut().version = request;
         self
     }
 
     /// Sets the desired OpenGL context profile.
     #[inline]
-    pub fn with_gl_profile(mut self, profile: GlProfile) -> Self {
-        self.gl_attr.profile = Some(profile);
+    fn with_gl_profile(mut self, profile: GlProfile) -> Self {
+        self.gl_attributes_mut().profile = Some(profile);
+        self
+    }
+
+    /// Sets the *forward-compatible* flag for the OpenGL context. See
+    /// `GlAttributes::forward_compatible`.
+    #[inline]
+    fn with_gl_forward_compatible(mut self, flag: bool) -> Self {
+        self.gl_attributes_mut().forward_compatible = flag;
         self
     }
 
@@ -211,78 +424,164 @@ impl<'a> ContextBuilder<'a> {
     /// The default value for this flag is `cfg!(debug_assertions)`, which means that it's enabled
     /// when you run `cargo build` and disabled when you run `cargo build --release`.
     #[inline]
-    pub fn with_gl_debug_flag(mut self, flag: bool) -> Self {
-        self.gl_attr.debug = flag;
+    fn with_gl_debug_flag(mut self, flag: bool) -> Self {
+        self.gl_attributes_mut().debug = flag;
         self
     }
 
     /// Sets the robustness of the OpenGL context. See the docs of `Robustness`.
     #[inline]
-    pub fn with_gl_robustness(mut self, robustness: Robustness) -> Self {
-        self.gl_attr.robustness = robustness;
+    fn with_gl_robustness(mut self, robustness: Robustness) -> Self {
+        self.gl_attributes_mut().robustness = robustness;
         self
     }
 
     /// Requests that the window has vsync enabled.
     ///
-    /// By default, vsync is not enabled.
+    /// By default, vsync is not enabled. Headless contexts ignore this setting.
+    #[inline]
+    fn with_vsync(mut self, vsync: bool) -> Self {
+        self.gl_attributes_mut().vsync = vsync;
+        self
+    }
+
+    /// Share the display lists with the given context.
+    ///
+    /// Headless contexts can only share their lists with contexts created by the same backend,
+    /// and not at all on Windows.
+    #[inline]
+    fn with_shared_lists(mut self, other: &'a Self::SharedContext) -> Self {
+        self.gl_attributes_mut().sharing = Some(other);
+        self
+    }
+
+    /// Builds the context into the given share group. See the docs of `ShareGroup`.
+    ///
+    /// Can't be combined with `with_shared_lists`.
+    #[inline]
+    fn with_share_group(mut self, group: &ShareGroup) -> Self {
+        *self.share_group_mut() = Some(group.clone());
+        self
+    }
+
+    /// Replaces all the pixel format requirements at once.
+    #[inline]
+    fn with_pixel_format_requirements(mut self, reqs: PixelFormatRequirements) -> Self {
+        *self.pixel_format_requirements_mut() = reqs;
+        self
+    }
+
+    /// Sets whether the pixel format must be hardware accelerated, or must not be. `None` means
+    /// "don't care".
+    ///
+    /// The default value is `Some(true)`.
+    #[inline]
+    fn with_hardware_acceleration(mut self, hardware_accelerated: Option<bool>) -> Self {
+        self.pixel_format_requirements_mut().hardware_accelerated = hardware_accelerated;
+        self
+    }
+
+    /// Sets whether the pixel format must be double-buffered, or single-buffered. `None` means
+    /// "don't care".
+    ///
+    /// The default value is `None`.
     #[inline]
-    pub fn with_vsync(mut self, vsync: bool) -> Self {
-        self.gl_attr.vsync = vsync;
+    fn with_double_buffer(mut self, double_buffer: Option<bool>) -> Self {
+        self.pixel_format_requirements_mut().double_buffer = double_buffer;
         self
     }
 
-    /// Share the display lists with the given `Context`.
+    /// Sets what happens when the context stops being current.
+    ///
+    /// The default value is `ReleaseBehavior::Flush`.
     #[inline]
-    pub fn with_shared_lists(mut self, other: &'a Context) -> Self {
-        self.gl_attr.sharing = Some(other);
+    fn with_release_behavior(mut self, behavior: ReleaseBehavior) -> Self {
+        self.pixel_format_requirements_mut().release_behavior = behavior;
         self
     }
 
     /// Sets the multisampling level to request. A value of `0` indicates that multisampling must
     /// not be enabled.
     ///
-    /// # Panic
-    ///
-    /// Will panic if `samples` is not a power of two.
+    /// Building returns `CreationError::InvalidPixelFormatRequirements` if `samples` is not a
+    /// power of two.
     #[inline]
-    pub fn with_multisampling(mut self, samples: u16) -> Self {
-        self.pf_reqs.multisampling = match samples {
+    fn with_multisampling(mut self, samples: u16) -> Self {
+        self.pixel_format_requirements_mut().multisampling = match samples {
             0 => None,
-            _ => {
-                assert!(samples.is_power_of_two());
-                Some(samples)
-            }
+            _ => Some(samples),
         };
         self
     }
 
     /// Sets the number of bits in the depth buffer.
     #[inline]
-    pub fn with_depth_buffer(mut self, bits: u8) -> Self {
-        self.pf_reqs.depth_bits = Some(bits);
+    fn with_depth_buffer(mut self, bits: u8) -> Self {
+        self.pixel_format_requirements_mut().depth_bits = Some(bits);
         self
     }
 
     /// Sets the number of bits in the stencil buffer.
     #[inline]
-    pub fn with_stencil_buffer(mut self, bits: u8) -> Self {
-        self.pf_reqs.stencil_bits = Some(bits);
+    fn with_stencil_buffer(mut self, bits: u8) -> Self {
+        self.pixel_format_requirements_mut().stencil_bits = Some(bits);
+        self
+    }
+
+    /// Sets the number of bits per channel in the accumulation buffer.
+    ///
+    /// Only OSMesa and GLX currently support accumulation buffers.
+    #[inline]
+    fn with_accumulation_buffer(mut self, bits: u8) -> Self {
+        self.pixel_format_requirements_mut().accum_bits = Some(bits);
         self
     }
 
     /// Sets the number of bits in the color buffer.
     #[inline]
-    pub fn with_pixel_format(mut self, color_bits: u8, alpha_bits: u8) -> Self {
-        self.pf_reqs.color_bits = Some(color_bits);
-        self.pf_reqs.alpha_bits = Some(alpha_bits);
+    fn with_pixel_format(mut self, color_bits: u8, alpha_bits: u8) -> Self {
+        {
+            let pf_reqs = self.pixel_format_requirements_mut();
+            pf_reqs.color_bits = Some(color_bits);
+            pf_reqs.alpha_bits = Some(alpha_bits);
+        }
+        self
+    }
+
+    /// Sets the number of bits of each channel of the color buffer, for example
+    /// `with_channel_bits(10, 10, 10, 2)` for a 30-bit color buffer.
+    #[inline]
+    fn with_channel_bits(mut self, red: u8, green: u8, blue: u8, alpha: u8) -> Self {
+        {
+            let pf_reqs = self.pixel_format_requirements_mut();
+            pf_reqs.channel_bits = Some((red, green, blue));
+            pf_reqs.alpha_bits = Some(alpha);
+        }
+        self
+    }
+
+    /// Sets the number of auxiliary color buffers.
+    ///
+    /// Only GLX currently supports auxiliary buffers.
+    #[inline]
+    fn with_aux_buffers(mut self, buffers: u8) -> Self {
+        self.pixel_format_requirements_mut().aux_buffers = Some(buffers);
+        self
+    }
+
+    /// Sets whether the color buffer must be in a floating-point format.
+    ///
+    /// The default value is `false`.
+    #[inline]
+    fn with_float_color_buffer(mut self, float_color_buffer: bool) -> Self {
+        self.pixel_format_requirements_mut().float_color_buffer = float_color_buffer;
         self
     }
 
     /// Request the backend to be stereoscopic.
     #[inline]
-    pub fn with_stereoscopy(mut self) -> Self {
-        self.pf_reqs.stereoscopy = true;
+    fn with_stereoscopy(mut self) -> Self {
+        self.pixel_format_requirements_mut().stereoscopy = true;
         self
     }
 
@@ -290,8 +589,33 @@ impl<'a> ContextBuilder<'a> {
     ///
     /// The default value is `false`.
     #[inline]
-    pub fn with_srgb(mut self, srgb_enabled: bool) -> Self {
-        self.pf_reqs.srgb = srgb_enabled;
+    fn with_srgb(mut self, srgb_enabled: bool) -> Self {
+        self.pixel_format_requirements_mut().srgb = srgb_enabled;
+        self
+    }
+
+    /// Uses the given configuration, as returned by `available_configs`, instead of choosing one
+    /// from the other pixel format requirements.
+    ///
+    /// Only GLX and EGL support choosing a configuration, the other backends return
+    /// `CreationError::NoAvailablePixelFormat`.
+    #[inline]
+    fn with_config(mut self, id: ConfigId) -> Self {
+        self.pixel_format_requirements_mut().config_id = Some(id);
+        self
+    }
+
+    /// Chooses the configuration with `rank` among the ones that satisfy the pixel format
+    /// requirements. `rank` returns the score of a configuration, or `None` to reject it, and the
+    /// configuration with the highest score is used.
+    ///
+    /// By default, the configuration that is the closest to the requirements is used, see
+    /// `select_config`. Only GLX and EGL support ranking the configurations.
+    #[inline]
+    fn with_config_ranking<F>(mut self, rank: F) -> Self
+        where F: Fn(&ConfigDescriptor) -> Option<u32> + Send + Sync + 'static
+    {
+        self.pixel_format_requirements_mut().ranking = Some(ConfigRanking::new(rank));
         self
     }
 }
@@ -308,13 +632,42 @@ impl GlWindow {
         events_loop: &EventsLoop,
     ) -> Result<Self, CreationError>
     {
-        let ContextBuilder { pf_reqs, gl_attr } = context_builder;
-        let gl_attr = gl_attr.map_sharing(|ctxt| &ctxt.context);
-        platform::Context::new(window_builder, events_loop, &pf_reqs, &gl_attr)
-            .map(|(window, context)| GlWindow {
-                window: window,
-                context: Context { context: context },
-            })
+        let ContextBuilder { pf_reqs, gl_attr, strict, share_group } = context_builder;
+        pf_reqs.validate()?;
+        let gl_attr = gl_attr.map_sharing(|ctxt| &*ctxt.context);
+        if gl_attr.sharing.is_some() && share_group.is_some() {
+            let msg = "Cannot use both `with_shared_lists` and `with_share_group`";
+            return Err(CreationError::PlatformSpecific(msg.into()));
+        }
+
+        let create = |shared: Option<&platform::Context>| {
+            let mut gl_attr = gl_attr.clone();
+            if shared.is_some() {
+                gl_attr.sharing = shared;
+            }
+            platform::Context::new(window_builder, events_loop, &pf_reqs, &gl_attr)
+                .map(|(window, context)| (Arc::new(context), window))
+        };
+        let (context, window) = match share_group {
+            Some(ref group) => group.build(create)?,
+            None => create(None)?,
+        };
+
+        let gl_window = GlWindow {
+            window: window,
+            context: Context {
+                context: context,
+                share_group: share_group,
+                gl_attr: gl_attr.clone().map_sharing(|_| ()),
+            },
+        };
+
+        if strict {
+            let vsync = gl_window.context.context.get_vsync();
+            strict::verify(&gl_window, &pf_reqs, &gl_attr, vsync)?;
+        }
+
+        Ok(gl_window)
     }
 
     /// Borrow the inner `Window`.
@@ -326,6 +679,65 @@ impl GlWindow {
     pub fn context(&self) -> &Context {
         &self.context
     }
+
+    /// Replaces the context of the window with a new one, built with the same `GlAttributes` and
+    /// pixel format, without destroying the window.
+    ///
+    /// This is how to recover when `get_reset_status` reports a graphics reset, or when
+    /// `make_current` or `swap_buffers` return `ContextError::ContextLost`. The OpenGL objects are
+    /// lost and must be created again in the new context, which must be made current.
+    ///
+    /// The new context doesn't share its objects with any other context and doesn't belong to a
+    /// share group, as a reset loses the objects of every context that shares them. The worker
+    /// contexts of `create_shared_worker_context` must be created again as well.
+    ///
+    /// Returns `CreationError::NotSupported` on Android, iOS and emscripten.
+    pub fn recreate_context(&mut self) -> Result<(), CreationError> {
+        let mut gl_attr = self.context.gl_attr.clone();
+        gl_attr.sharing = None;
+        let gl_attr = gl_attr.map_sharing(|_| unreachable!());
+
+        let context = self.context.context.recreate(&self.window, &gl_attr)?;
+        self.context.context = Arc::new(context);
+        self.context.share_group = None;
+        Ok(())
+    }
+
+    /// Creates a headless context that shares its objects with the context of the window.
+    ///
+    /// The context uses the same OpenGL version, profile, debug flag and robustness as the
+    /// context of the window, and has a 1x1 framebuffer. It can be moved to another thread, for
+    /// example to upload textures while the window keeps rendering.
+    ///
+    /// On Linux, the context renders to a pbuffer on X11 and to a framebuffer object on Wayland.
+    /// On Windows, it uses an EGL pbuffer or a hidden window, and `HeadlessContext` isn't `Send`
+    /// because the hidden window must stay on the thread that created it: the context must be
+    /// used on the thread of the window. Returns `CreationError::NotSupported` on iOS and
+    /// emscripten.
+    pub fn create_shared_worker_context(&self) -> Result<HeadlessContext, CreationError> {
+        let dimensions = (1, 1);
+        let pf_reqs = PixelFormatRequirements {
+            hardware_accelerated: None,
+            .. Default::default()
+        };
+        let mut gl_attr = self.context.gl_attr.clone();
+        // the window may itself share its objects with another context
+        gl_attr.sharing = None;
+        let mut gl_attr = gl_attr.map_sharing(|_| unreachable!());
+        gl_attr.sharing = Some(&*self.context.context);
+
+        platform::HeadlessContext::new_shared_with_window(&self.window, dimensions, &pf_reqs,
+                                                          &gl_attr)
+            .map(|context| HeadlessContext::from_platform(context, dimensions))
+    }
+}
+
+impl Context {
+    /// Returns the share group the context was built into, if any.
+    #[inline]
+    pub fn share_group(&self) -> Option<&ShareGroup> {
+        self.share_group.as_ref()
+    }
 }
 
 impl GlContext for Context {
@@ -353,6 +765,14 @@ impl GlContext for Context {
         self.context.get_pixel_format()
     }
 
+    fn get_context_info(&self) -> ContextInfo {
+        self.context.get_context_info()
+    }
+
+    fn get_platform_extensions(&self) -> &Extensions {
+        self.context.get_platform_extensions()
+    }
+
     fn resize(&self, width: u32, height: u32) {
         self.context.resize(width, height);
     }
@@ -383,6 +803,14 @@ impl GlContext for GlWindow {
         self.context.get_pixel_format()
     }
 
+    fn get_context_info(&self) -> ContextInfo {
+        self.context.get_context_info()
+    }
+
+    fn get_platform_extensions(&self) -> &Extensions {
+        self.context.get_platform_extensions()
+    }
+
     fn resize(&self, width: u32, height: u32) {
         self.context.resize(width, height);
     }
@@ -407,6 +835,12 @@ pub enum CreationError {
     NoAvailablePixelFormat,
     PlatformSpecific(String),
     Window(WindowCreationError),
+    /// The context was created, but doesn't fulfill some of the requirements. Only returned when
+    /// building in strict mode. Contains a description of each unmet requirement.
+    RequirementsNotMet(Vec<String>),
+    /// The `PixelFormatRequirements` can't be satisfied by any pixel format, for example a
+    /// floating-point sRGB color buffer. Contains a description of the problem.
+    InvalidPixelFormatRequirements(String),
 }
 
 impl CreationError {
@@ -423,6 +857,10 @@ impl CreationError {
                                                       the criterias.",
             CreationError::PlatformSpecific(ref text) => &text,
             CreationError::Window(ref err) => std::error::Error::description(err),
+            CreationError::RequirementsNotMet(_) => "Some of the requirements are not met by \
+                                                     the created context.",
+            CreationError::InvalidPixelFormatRequirements(_) => "The pixel format requirements \
+                                                                 are invalid.",
         }
     }
 }
@@ -430,6 +868,15 @@ impl CreationError {
 impl std::fmt::Display for CreationError {
     fn fmt(&self, formatter: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
         formatter.write_str(self.to_string())?;
+        match *self {
+            CreationError::RequirementsNotMet(ref unmet) => {
+                write!(formatter, " {}", unmet.join("; "))?;
+            },
+            CreationError::InvalidPixelFormatRequirements(ref problem) => {
+                write!(formatter, " {}", problem)?;
+            },
+            _ => (),
+        }
         if let Some(err) = std::error::Error::cause(self) {
             write!(formatter, ": {}", err)?;
         }
@@ -461,7 +908,13 @@ impl From<WindowCreationError> for CreationError {
 #[derive(Debug)]
 pub enum ContextError {
     IoError(io::Error),
+    /// A native function failed. Contains a description of the error.
+    OsError(String),
+    /// The context was lost, for example after a graphics reset or because the display went to
+    /// sleep, and must be recreated. See `GlWindow::recreate_context`.
     ContextLost,
+    /// The operation requires the context to be current in the calling thread, and it isn't.
+    NotCurrent,
 }
 
 impl ContextError {
@@ -469,7 +922,9 @@ impl ContextError {
         use std::error::Error;
         match *self {
             ContextError::IoError(ref err) => err.description(),
-            ContextError::ContextLost => "Context lost"
+            ContextError::OsError(ref text) => &text,
+            ContextError::ContextLost => "Context lost",
+            ContextError::NotCurrent => "The context is not current"
         }
     }
 }
@@ -571,8 +1026,8 @@ pub enum Robustness {
     TryRobustNoResetNotification,
 
     /// Everything is checked to avoid any crash. If a problem occurs, the context will enter a
-    /// "context lost" state. It must then be recreated. For the moment, glutin doesn't provide a
-    /// way to recreate a context with the same window :-/
+    /// "context lost" state, which `GlContext::get_reset_status` reports. It must then be
+    /// recreated, with `GlWindow::recreate_context` for the context of a window.
     RobustLoseContextOnReset,
 
     /// Same as `RobustLoseContextOnReset` but the context creation doesn't fail if it's not
@@ -595,14 +1050,60 @@ pub enum ReleaseBehavior {
 #[derive(Debug, Clone)]
 pub struct PixelFormat {
     pub hardware_accelerated: bool,
+    /// Sum of `red_bits`, `green_bits` and `blue_bits`.
     pub color_bits: u8,
+    pub red_bits: u8,
+    pub green_bits: u8,
+    pub blue_bits: u8,
     pub alpha_bits: u8,
     pub depth_bits: u8,
     pub stencil_bits: u8,
+    /// Number of bits per channel of the accumulation buffer, `0` if there is none.
+    pub accum_bits: u8,
+    /// Number of auxiliary color buffers.
+    pub aux_buffers: u8,
     pub stereoscopy: bool,
     pub double_buffer: bool,
     pub multisampling: Option<u16>,
     pub srgb: bool,
+    /// True if the color buffer is in a floating-point format.
+    pub float_color_buffer: bool,
+    /// True if the alpha channel is used by the window system to blend the window with what is
+    /// behind it, see `WindowBuilder::with_transparency`.
+    pub transparent: bool,
+    /// The behavior when the context stops being current. `Flush` if `ReleaseBehavior::None` was
+    /// requested but isn't supported.
+    pub release_behavior: ReleaseBehavior,
+    /// The id of the visual of the window system, such as an X11 `VisualID`, if any.
+    pub native_visual_id: Option<u32>,
+    /// The configuration of the format, for the GLX and EGL backends.
+    pub config_id: Option<ConfigId>,
+}
+
+/// Describes the context that was created, as negotiated with the window system.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContextInfo {
+    /// The version of OpenGL or OpenGL ES of the context, which may be later than the requested
+    /// one. GLX and EGL read it from `GL_VERSION` when the context is created.
+    ///
+    /// The other backends report the version that was requested from the window system. With
+    /// `GlRequest::Latest`, this is the first version of the fallback list that was accepted, and
+    /// `(1, 0)` if the backend couldn't request a version.
+    pub version: (u8, u8),
+
+    /// The profile of the context. `None` for OpenGL ES, for OpenGL versions before 3.2, which
+    /// don't have profiles, and if the backend couldn't request one.
+    pub profile: Option<GlProfile>,
+
+    /// True if the *debug* flag is set.
+    pub debug: bool,
+
+    /// True if the *forward-compatible* flag is set.
+    pub forward_compatible: bool,
+
+    /// How the context detects errors. This is never one of the `Try*` variants: they are
+    /// replaced by the robustness that was actually enabled.
+    pub robustness: Robustness,
 }
 
 /// Describes how the backend should choose a pixel format.
@@ -617,6 +1118,11 @@ pub struct PixelFormatRequirements {
     /// The default is `Some(24)`.
     pub color_bits: Option<u8>,
 
+    /// Minimum number of bits for the red, green and blue channels of the color buffer, for
+    /// example `Some((10, 10, 10))` for a 30-bit color buffer. If set, `color_bits` is ignored.
+    /// The default is `None`.
+    pub channel_bits: Option<(u8, u8, u8)>,
+
     /// If true, the color buffer must be in a floating point format. Default is `false`.
     ///
     /// Using floating points allows you to write values outside of the `[0.0, 1.0]` range.
@@ -634,6 +1140,15 @@ pub struct PixelFormatRequirements {
     /// The default value is `Some(8)`.
     pub stencil_bits: Option<u8>,
 
+    /// Minimum number of bits per channel of the accumulation buffer. `None` means that no
+    /// accumulation buffer is needed. Only OSMesa and GLX currently honor this value.
+    /// The default value is `None`.
+    pub accum_bits: Option<u8>,
+
+    /// Minimum number of auxiliary color buffers. `None` means that none are needed. Only GLX
+    /// currently honors this value. The default value is `None`.
+    pub aux_buffers: Option<u8>,
+
     /// If true, only double-buffered formats will be considered. If false, only single-buffer
     /// formats. `None` means "don't care". The default is `Some(true)`.
     pub double_buffer: Option<bool>,
@@ -653,6 +1168,16 @@ pub struct PixelFormatRequirements {
 
     /// The behavior when changing the current context. Default is `Flush`.
     pub release_behavior: ReleaseBehavior,
+
+    /// A configuration returned by `available_configs`, to use instead of choosing one with the
+    /// other requirements, which are then ignored. Only the GLX and EGL backends support it, the
+    /// other backends return `NoAvailablePixelFormat`. The default is `None`.
+    pub config_id: Option<ConfigId>,
+
+    /// Ranks the configurations that satisfy the other requirements, instead of choosing the
+    /// closest one. See `select_config`. Only the GLX and EGL backends use it. The default is
+    /// `None`.
+    pub ranking: Option<ConfigRanking>,
 }
 
 impl Default for PixelFormatRequirements {
@@ -661,15 +1186,62 @@ impl Default for PixelFormatRequirements {
         PixelFormatRequirements {
             hardware_accelerated: Some(true),
             color_bits: Some(24),
+            channel_bits: None,
             float_color_buffer: false,
             alpha_bits: Some(8),
             depth_bits: Some(24),
             stencil_bits: Some(8),
+            accum_bits: None,
+            aux_buffers: None,
             double_buffer: None,
             multisampling: None,
             stereoscopy: false,
             srgb: false,
             release_behavior: ReleaseBehavior::Flush,
+            config_id: None,
+            ranking: None,
+        }
+    }
+}
+
+impl PixelFormatRequirements {
+    /// Checks that the requirements don't contradict each other. This is done when building a
+    /// context.
+    pub fn validate(&self) -> Result<(), CreationError> {
+        let invalid = |problem: &str| {
+            Err(CreationError::InvalidPixelFormatRequirements(problem.into()))
+        };
+
+        match self.multisampling {
+            Some(samples) if samples != 0 && !samples.is_power_of_two() => {
+                return invalid("the number of samples must be a power of two");
+            },
+            _ => (),
+        }
+
+        if self.float_color_buffer && self.srgb {
+            return invalid("a floating-point color buffer can't be sRGB");
+        }
+
+        if let Some((red, green, blue)) = self.channel_bits {
+            if red as u32 + green as u32 + blue as u32 > 255 {
+                return invalid("the channels can't have more than 255 bits in total");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the minimum number of bits of the red, green and blue channels, from
+    /// `channel_bits` or by splitting `color_bits`.
+    pub(crate) fn rgb_bits(&self) -> Option<(u8, u8, u8)> {
+        match (self.channel_bits, self.color_bits) {
+            (Some(channels), _) => Some(channels),
+            // green gets the extra bit first, like in RGB565
+            (None, Some(color)) => Some((color / 3,
+                                         color / 3 + if color % 3 != 0 { 1 } else { 0 },
+                                         color / 3 + if color % 3 == 2 { 1 } else { 0 })),
+            (None, None) => None,
         }
     }
 }
@@ -692,6 +1264,12 @@ pub struct GlAttributes<S> {
     /// The default is `None`.
     pub profile: Option<GlProfile>,
 
+    /// Whether to enable the *forward-compatible* flag of the context, which removes the
+    /// deprecated functionality of desktop OpenGL 3.0 and above. Only GLX, WGL and EGL honor it.
+    ///
+    /// The default is `false`.
+    pub forward_compatible: bool,
+
     /// Whether to enable the `debug` flag of the context.
     ///
     /// Debug contexts are usually slower but give better error reporting.
@@ -720,6 +1298,7 @@ impl<S> GlAttributes<S> {
             sharing: self.sharing.map(f),
             version: self.version,
             profile: self.profile,
+            forward_compatible: self.forward_compatible,
             debug: self.debug,
             robustness: self.robustness,
             vsync: self.vsync,
@@ -734,6 +1313,7 @@ impl<S> Default for GlAttributes<S> {
             sharing: None,
             version: GlRequest::Latest,
             profile: None,
+            forward_compatible: false,
             debug: cfg!(debug_assertions),
             robustness: Robustness::NotRobust,
             vsync: false,
@@ -2,7 +2,8 @@
 
 pub use api::egl::ffi::EGLContext;
 pub use api::glx::ffi::GLXContext;
-pub use platform::RawHandle;
+pub use api::osmesa::ffi::OSMesaContext;
+pub use platform::{HeadlessBackend, RawHandle};
 
 pub use winit::os::unix::XNotSupported;
 pub use winit::os::unix::EventsLoopExt;
@@ -10,11 +11,9 @@ pub use winit::os::unix::MonitorIdExt;
 pub use winit::os::unix::WindowBuilderExt;
 pub use winit::os::unix::WindowExt;
 
-use {Context, HeadlessContext};
+use {Context, HeadlessContext, HeadlessRendererBuilder};
 use os::GlContextExt;
 
-use std::os::raw::c_void;
-
 impl GlContextExt for Context {
     type Handle = RawHandle;
 
@@ -25,10 +24,50 @@ impl GlContextExt for Context {
 }
 
 impl GlContextExt for HeadlessContext {
-    type Handle = *mut c_void;
+    type Handle = RawHandle;
 
     #[inline]
     unsafe fn raw_handle(&self) -> Self::Handle {
         self.context.raw_handle()
     }
 }
+
+/// Additional methods on `HeadlessRendererBuilder` that are specific to Unix.
+pub trait HeadlessRendererBuilderExt {
+    /// Only use the given backend to create the context.
+    fn with_headless_backend(self, backend: HeadlessBackend) -> Self;
+
+    /// Sets the backends to try when creating the context, in order of preference.
+    ///
+    /// The first backend that manages to create a context is used. If all of them fail, the
+    /// error of each backend is reported.
+    ///
+    /// The default is `EglSurfaceless`, `EglPbuffer`, `GlxPbuffer` and finally `OsMesa`.
+    fn with_headless_backends(self, backends: Vec<HeadlessBackend>) -> Self;
+}
+
+impl<'a> HeadlessRendererBuilderExt for HeadlessRendererBuilder<'a> {
+    #[inline]
+    fn with_headless_backend(self, backend: HeadlessBackend) -> Self {
+        self.with_headless_backends(vec![backend])
+    }
+
+    #[inline]
+    fn with_headless_backends(mut self, backends: Vec<HeadlessBackend>) -> Self {
+        self.platform_specific.backends = backends;
+        self
+    }
+}
+
+/// Additional methods on `HeadlessContext` that are specific to Unix.
+pub trait HeadlessContextExt {
+    /// Returns the backend that was used to create the context.
+    fn get_headless_backend(&self) -> HeadlessBackend;
+}
+
+impl HeadlessContextExt for HeadlessContext {
+    #[inline]
+    fn get_headless_backend(&self) -> HeadlessBackend {
+        self.context.get_backend()
+    }
+}
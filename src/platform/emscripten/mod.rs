@@ -2,8 +2,8 @@
 
 use std::ffi::CString;
 
-use {Api, ContextError, CreationError, GlAttributes, GlRequest};
-use {PixelFormat, PixelFormatRequirements};
+use {Api, ConfigDescriptor, ContextError, ContextInfo, CreationError, Extensions, GlAttributes};
+use {GlRequest, PixelFormat, PixelFormatRequirements, ReleaseBehavior, Robustness};
 
 use winit;
 
@@ -11,6 +11,15 @@ mod ffi;
 
 pub struct Context {
     context: ffi::EMSCRIPTEN_WEBGL_CONTEXT_HANDLE,
+    version: (u8, u8),
+    // WebGL doesn't have window system extensions
+    extensions: Extensions,
+}
+
+/// See the docs in the crate root file.
+#[inline]
+pub fn available_configs(_: &winit::EventsLoop) -> Result<Vec<ConfigDescriptor>, CreationError> {
+    Err(CreationError::NotSupported)
 }
 
 impl Context {
@@ -22,6 +31,9 @@ impl Context {
         gl_attr: &GlAttributes<&Context>,
     ) -> Result<(winit::Window, Self), CreationError>
     {
+        if pf_reqs.config_id.is_some() {
+            return Err(CreationError::NoAvailablePixelFormat);
+        }
         let window = window_builder.build(events_loop)?;
 
         // getting the default values of attributes
@@ -53,12 +65,21 @@ impl Context {
         // TODO: emscripten_set_webglcontextrestored_callback
 
         let ctxt = Context {
-            context: context
+            context: context,
+            version: (attributes.majorVersion as u8, attributes.minorVersion as u8),
+            extensions: Extensions::default(),
         };
 
         Ok((window, ctxt))
     }
 
+    #[inline]
+    pub fn recreate(&self, _: &winit::Window, _: &GlAttributes<&Context>)
+                    -> Result<Self, CreationError>
+    {
+        Err(CreationError::NotSupported)
+    }
+
     #[inline]
     pub fn resize(&self, width: u32, height: u32) {
         // TODO: ?
@@ -104,20 +125,51 @@ impl Context {
         PixelFormat {
             hardware_accelerated: true,
             color_bits: 24,
+            red_bits: 8,
+            green_bits: 8,
+            blue_bits: 8,
             alpha_bits: 8,
             depth_bits: 24,
             stencil_bits: 8,
+            accum_bits: 0,
+            aux_buffers: 0,
             stereoscopy: false,
             double_buffer: true,
             multisampling: None,
             srgb: true,
+            float_color_buffer: false,
+            transparent: false,
+            release_behavior: ReleaseBehavior::Flush,
+            native_visual_id: None,
+            config_id: None,
+        }
+    }
+
+    #[inline]
+    pub fn get_context_info(&self) -> ContextInfo {
+        ContextInfo {
+            version: self.version,
+            profile: None,
+            debug: false,
+            forward_compatible: false,
+            robustness: Robustness::NotRobust,
         }
     }
 
+    #[inline]
+    pub fn get_platform_extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
     #[inline]
     pub unsafe fn raw_handle(&self) -> ffi::EMSCRIPTEN_WEBGL_CONTEXT_HANDLE {
         self.context
     }
+
+    #[inline]
+    pub fn get_vsync(&self) -> Option<bool> {
+        None
+    }
 }
 
 impl Drop for Context {
@@ -136,6 +188,14 @@ pub struct HeadlessContext {
 }
 
 impl HeadlessContext {
+    /// See the docs in the crate root file.
+    #[inline]
+    pub fn available_configs(_: &PlatformSpecificHeadlessBuilderAttributes)
+                             -> Result<Vec<ConfigDescriptor>, CreationError>
+    {
+        Err(CreationError::NotSupported)
+    }
+
     pub fn new(dimensions: (u32, u32), pf_reqs: &PixelFormatRequirements,
                opengl: &GlAttributes<&HeadlessContext>,
                _: &PlatformSpecificHeadlessBuilderAttributes)
@@ -144,6 +204,14 @@ impl HeadlessContext {
         unimplemented!()
     }
 
+    #[inline]
+    pub fn new_shared_with_window(_: &winit::Window, _: (u32, u32), _: &PixelFormatRequirements,
+                                  _: &GlAttributes<&Context>)
+                                  -> Result<HeadlessContext, CreationError>
+    {
+        Err(CreationError::NotSupported)
+    }
+
     #[inline]
     pub unsafe fn make_current(&self) -> Result<(), ContextError> {
         // TOOD: check if == EMSCRIPTEN_RESULT
@@ -183,10 +251,31 @@ impl HeadlessContext {
         unimplemented!()
     }
 
+    #[inline]
+    pub fn get_context_info(&self) -> ContextInfo {
+        unimplemented!()
+    }
+
+    #[inline]
+    pub fn get_platform_extensions(&self) -> &Extensions {
+        unimplemented!()
+    }
+
     #[inline]
     pub unsafe fn raw_handle(&self) -> ffi::EMSCRIPTEN_WEBGL_CONTEXT_HANDLE {
         self.context
     }
+
+    #[inline]
+    pub fn resize(&self, _width: u32, _height: u32) -> Result<(), CreationError> {
+        // TODO: ?
+        Ok(())
+    }
+
+    #[inline]
+    pub unsafe fn resolve_framebuffer(&self) -> u32 {
+        0
+    }
 }
 
 fn error_to_str(code: ffi::EMSCRIPTEN_RESULT) -> &'static str {
@@ -4,20 +4,37 @@ pub use api::ios::*;
 
 pub use cocoa::base::id;
 
+use ConfigDescriptor;
 use GlAttributes;
 use CreationError;
 use PixelFormat;
 use PixelFormatRequirements;
 use ContextError;
+use ContextInfo;
+use Extensions;
 
 use std::os::raw::c_void;
 
+/// See the docs in the crate root file.
+#[inline]
+pub fn available_configs(_: &::winit::EventsLoop) -> Result<Vec<ConfigDescriptor>, CreationError> {
+    Err(CreationError::NotSupported)
+}
+
 #[derive(Clone, Default)]
 pub struct PlatformSpecificHeadlessBuilderAttributes;
 
 pub struct HeadlessContext(i32);
 
 impl HeadlessContext {
+    /// See the docs in the crate root file.
+    #[inline]
+    pub fn available_configs(_: &PlatformSpecificHeadlessBuilderAttributes)
+                             -> Result<Vec<ConfigDescriptor>, CreationError>
+    {
+        Err(CreationError::NotSupported)
+    }
+
     /// See the docs in the crate root file.
     pub fn new(_: (u32, u32), _: &PixelFormatRequirements, _: &GlAttributes<&HeadlessContext>,
                _: &PlatformSpecificHeadlessBuilderAttributes)
@@ -26,6 +43,14 @@ impl HeadlessContext {
         unimplemented!()
     }
 
+    /// See the docs in the crate root file.
+    pub fn new_shared_with_window(_: &::winit::Window, _: (u32, u32), _: &PixelFormatRequirements,
+                                  _: &GlAttributes<&Context>)
+                                  -> Result<HeadlessContext, CreationError>
+    {
+        Err(CreationError::NotSupported)
+    }
+
     /// See the docs in the crate root file.
     pub unsafe fn make_current(&self) -> Result<(), ContextError> {
         unimplemented!()
@@ -53,9 +78,26 @@ impl HeadlessContext {
         unimplemented!()
     }
 
+    pub fn get_context_info(&self) -> ContextInfo {
+        unimplemented!()
+    }
+
+    pub fn get_platform_extensions(&self) -> &Extensions {
+        unimplemented!()
+    }
+
     pub unsafe fn raw_handle(&self) -> *mut c_void {
         unimplemented!()
     }
+
+    pub fn resize(&self, _width: u32, _height: u32) -> Result<(), CreationError> {
+        // No sense on iOS
+        Ok(())
+    }
+
+    pub unsafe fn resolve_framebuffer(&self) -> u32 {
+        unimplemented!()
+    }
 }
 
 unsafe impl Send for HeadlessContext {}
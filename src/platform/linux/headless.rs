@@ -0,0 +1,490 @@
+use {Api, ConfigDescriptor, ContextError, CreationError, GlAttributes, PixelFormat};
+use {ContextInfo, Extensions, PixelFormatRequirements};
+use api::egl::{self, Context as EglContext};
+use api::glx::{self, Context as GlxContext};
+use api::glx::ffi::{Display, Xlib};
+use api::osmesa::OsMesaContext;
+
+use super::{Context, GlxOrEgl, RawHandle};
+use super::x11::XConnection;
+use super::surfaceless::SurfacelessContext;
+
+use std::{error, fmt, ptr};
+use std::sync::Arc;
+
+use winit;
+
+/// The backends that can provide a headless context on Linux.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum HeadlessBackend {
+    /// Software rendering with OSMesa.
+    OsMesa,
+    /// An EGL pbuffer surface on the default EGL display.
+    EglPbuffer,
+    /// An EGL context without any surface, rendering into a framebuffer object. Requires
+    /// `EGL_MESA_platform_surfaceless` and `EGL_KHR_surfaceless_context`.
+    EglSurfaceless,
+    /// A GLX pbuffer on the default X display.
+    GlxPbuffer,
+}
+
+#[derive(Clone)]
+pub struct PlatformSpecificHeadlessBuilderAttributes {
+    /// The backends to try, in order. The first one that succeeds is used.
+    pub backends: Vec<HeadlessBackend>,
+}
+
+impl Default for PlatformSpecificHeadlessBuilderAttributes {
+    #[inline]
+    fn default() -> Self {
+        PlatformSpecificHeadlessBuilderAttributes {
+            backends: vec![
+                HeadlessBackend::EglSurfaceless,
+                HeadlessBackend::EglPbuffer,
+                HeadlessBackend::GlxPbuffer,
+                HeadlessBackend::OsMesa,
+            ],
+        }
+    }
+}
+
+pub enum HeadlessContext {
+    /// A software-rendered OSMesa context.
+    OsMesa(OsMesaContext),
+    /// An EGL pbuffer.
+    EglPbuffer(EglContext),
+    /// An EGL context without any surface, rendering into a framebuffer object.
+    EglSurfaceless(SurfacelessContext),
+    /// A GLX pbuffer.
+    GlxPbuffer(GlxPbufferContext),
+}
+
+impl HeadlessContext {
+    pub fn new(dimensions: (u32, u32), pf_reqs: &PixelFormatRequirements,
+               opengl: &GlAttributes<&HeadlessContext>,
+               attributes: &PlatformSpecificHeadlessBuilderAttributes)
+               -> Result<HeadlessContext, CreationError>
+    {
+        let libraries = GlxOrEgl::new();
+
+        // trying each backend in order, and keeping the errors in case all of them fail
+        let mut errors = Vec::new();
+        for &backend in &attributes.backends {
+            match HeadlessContext::new_with_backend(backend, dimensions, pf_reqs, opengl,
+                                                    &libraries)
+            {
+                Ok(context) => return Ok(context),
+                Err(err) => errors.push((backend, err)),
+            }
+        }
+
+        Err(backends_failed(errors))
+    }
+
+    /// Lists the configurations of the EGL and GLX backends among `attributes.backends`. OSMesa
+    /// doesn't have any.
+    pub fn available_configs(attributes: &PlatformSpecificHeadlessBuilderAttributes)
+                             -> Result<Vec<ConfigDescriptor>, CreationError>
+    {
+        let libraries = GlxOrEgl::new();
+
+        let mut configs = Vec::new();
+        let mut errors = Vec::new();
+        for &backend in &attributes.backends {
+            match HeadlessContext::backend_configs(backend, &libraries) {
+                Ok(backend_configs) => configs.extend(backend_configs),
+                Err(err) => errors.push((backend, err)),
+            }
+        }
+
+        if configs.is_empty() && !errors.is_empty() {
+            return Err(backends_failed(errors));
+        }
+        Ok(configs)
+    }
+
+    fn backend_configs(backend: HeadlessBackend, libraries: &GlxOrEgl)
+                       -> Result<Vec<ConfigDescriptor>, CreationError>
+    {
+        let configs = match backend {
+            HeadlessBackend::OsMesa => Vec::new(),
+
+            HeadlessBackend::EglPbuffer => {
+                let egl = libraries.egl.as_ref().ok_or_else(|| library_not_found("libEGL"))?;
+                egl::available_configs(egl, egl::NativeDisplay::Other(None))?
+            },
+
+            HeadlessBackend::EglSurfaceless => {
+                let egl = libraries.egl.as_ref().ok_or_else(|| library_not_found("libEGL"))?;
+                // the buffers are allocated with the formats of the chosen configuration
+                return egl::available_configs(egl, egl::NativeDisplay::Surfaceless);
+            },
+
+            HeadlessBackend::GlxPbuffer => {
+                let glx = libraries.glx.as_ref().ok_or_else(|| library_not_found("libGL"))?;
+                let display = XDisplay::open()?;
+                unsafe {
+                    let screen_id = (display.xlib.XDefaultScreen)(display.display);
+                    glx::available_configs(glx, &display.xlib, display.display, screen_id)
+                }
+            },
+        };
+
+        Ok(configs.into_iter().filter(|config| config.pbuffer).collect())
+    }
+
+    fn new_with_backend(backend: HeadlessBackend, dimensions: (u32, u32),
+                        pf_reqs: &PixelFormatRequirements,
+                        opengl: &GlAttributes<&HeadlessContext>, libraries: &GlxOrEgl)
+                        -> Result<HeadlessContext, CreationError>
+    {
+        if let Some(ctxt) = opengl.sharing {
+            if ctxt.get_backend() != backend {
+                let msg = format!("Cannot share a {:?} context with a {:?} context",
+                                  backend, ctxt.get_backend());
+                return Err(CreationError::PlatformSpecific(msg));
+            }
+        }
+
+        match backend {
+            HeadlessBackend::OsMesa => {
+                let opengl = opengl.clone().map_sharing(|ctxt| match *ctxt {
+                    HeadlessContext::OsMesa(ref ctxt) => ctxt,
+                    _ => unreachable!(),
+                });
+                OsMesaContext::new(dimensions, pf_reqs, &opengl).map(HeadlessContext::OsMesa)
+            },
+
+            HeadlessBackend::EglPbuffer => {
+                let egl = match libraries.egl {
+                    Some(ref egl) => egl.clone(),
+                    None => return Err(library_not_found("libEGL")),
+                };
+                let opengl = opengl.clone().map_sharing(|ctxt| match *ctxt {
+                    HeadlessContext::EglPbuffer(ref ctxt) => ctxt,
+                    _ => unreachable!(),
+                });
+                EglContext::new(egl, pf_reqs, &opengl, egl::NativeDisplay::Other(None),
+                                egl::SurfaceType::PBuffer)
+                    .and_then(|prototype| prototype.finish_pbuffer(dimensions))
+                    .map(HeadlessContext::EglPbuffer)
+            },
+
+            HeadlessBackend::EglSurfaceless => {
+                let egl = match libraries.egl {
+                    Some(ref egl) => egl.clone(),
+                    None => return Err(library_not_found("libEGL")),
+                };
+                let opengl = opengl.clone().map_sharing(|ctxt| match *ctxt {
+                    HeadlessContext::EglSurfaceless(ref ctxt) => ctxt.egl_context(),
+                    _ => unreachable!(),
+                });
+                SurfacelessContext::new(egl, egl::NativeDisplay::Surfaceless, dimensions, pf_reqs,
+                                        &opengl)
+                    .map(HeadlessContext::EglSurfaceless)
+            },
+
+            HeadlessBackend::GlxPbuffer => {
+                let glx = match libraries.glx {
+                    Some(ref glx) => glx.clone(),
+                    None => return Err(library_not_found("libGL")),
+                };
+                let opengl = opengl.clone().map_sharing(|ctxt| match *ctxt {
+                    HeadlessContext::GlxPbuffer(ref ctxt) => ctxt,
+                    _ => unreachable!(),
+                });
+                GlxPbufferContext::new(glx, dimensions, pf_reqs, &opengl)
+                    .map(HeadlessContext::GlxPbuffer)
+            },
+        }
+    }
+
+    /// Creates a context that shares its objects with the context of a window. `opengl.sharing`
+    /// must be the context of `window`.
+    ///
+    /// The context renders to a pbuffer on X11, and to a framebuffer object on Wayland.
+    pub fn new_shared_with_window(window: &winit::Window, dimensions: (u32, u32),
+                                  pf_reqs: &PixelFormatRequirements,
+                                  opengl: &GlAttributes<&Context>)
+                                  -> Result<HeadlessContext, CreationError>
+    {
+        match opengl.sharing {
+            Some(&Context::X(ref ctxt)) => {
+                let opengl = opengl.clone().map_sharing(|_| ctxt);
+                ctxt.new_shared_headless(dimensions, pf_reqs, &opengl)
+            },
+            Some(&Context::Wayland(ref ctxt)) => {
+                let opengl = opengl.clone().map_sharing(|_| ctxt);
+                ctxt.new_shared_headless(window, dimensions, pf_reqs, &opengl)
+            },
+            None => unreachable!(),
+        }
+    }
+
+    /// Returns the backend that provides this context.
+    #[inline]
+    pub fn get_backend(&self) -> HeadlessBackend {
+        match *self {
+            HeadlessContext::OsMesa(_) => HeadlessBackend::OsMesa,
+            HeadlessContext::EglPbuffer(_) => HeadlessBackend::EglPbuffer,
+            HeadlessContext::EglSurfaceless(_) => HeadlessBackend::EglSurfaceless,
+            HeadlessContext::GlxPbuffer(_) => HeadlessBackend::GlxPbuffer,
+        }
+    }
+
+    #[inline]
+    pub unsafe fn make_current(&self) -> Result<(), ContextError> {
+        match *self {
+            HeadlessContext::OsMesa(ref ctxt) => ctxt.make_current(),
+            HeadlessContext::EglPbuffer(ref ctxt) => ctxt.make_current(),
+            HeadlessContext::EglSurfaceless(ref ctxt) => ctxt.make_current(),
+            HeadlessContext::GlxPbuffer(ref ctxt) => ctxt.context.make_current(),
+        }
+    }
+
+    #[inline]
+    pub fn is_current(&self) -> bool {
+        match *self {
+            HeadlessContext::OsMesa(ref ctxt) => ctxt.is_current(),
+            HeadlessContext::EglPbuffer(ref ctxt) => ctxt.is_current(),
+            HeadlessContext::EglSurfaceless(ref ctxt) => ctxt.is_current(),
+            HeadlessContext::GlxPbuffer(ref ctxt) => ctxt.context.is_current(),
+        }
+    }
+
+    #[inline]
+    pub fn get_proc_address(&self, addr: &str) -> *const () {
+        match *self {
+            HeadlessContext::OsMesa(ref ctxt) => ctxt.get_proc_address(addr),
+            HeadlessContext::EglPbuffer(ref ctxt) => ctxt.get_proc_address(addr),
+            HeadlessContext::EglSurfaceless(ref ctxt) => ctxt.get_proc_address(addr),
+            HeadlessContext::GlxPbuffer(ref ctxt) => ctxt.context.get_proc_address(addr),
+        }
+    }
+
+    #[inline]
+    pub fn swap_buffers(&self) -> Result<(), ContextError> {
+        match *self {
+            HeadlessContext::OsMesa(ref ctxt) => ctxt.swap_buffers(),
+            HeadlessContext::EglPbuffer(ref ctxt) => ctxt.swap_buffers(),
+            HeadlessContext::EglSurfaceless(ref ctxt) => ctxt.swap_buffers(),
+            HeadlessContext::GlxPbuffer(ref ctxt) => ctxt.context.swap_buffers(),
+        }
+    }
+
+    #[inline]
+    pub fn get_api(&self) -> Api {
+        match *self {
+            HeadlessContext::OsMesa(ref ctxt) => ctxt.get_api(),
+            HeadlessContext::EglPbuffer(ref ctxt) => ctxt.get_api(),
+            HeadlessContext::EglSurfaceless(ref ctxt) => ctxt.get_api(),
+            HeadlessContext::GlxPbuffer(ref ctxt) => ctxt.context.get_api(),
+        }
+    }
+
+    #[inline]
+    pub fn get_pixel_format(&self) -> PixelFormat {
+        match *self {
+            HeadlessContext::OsMesa(ref ctxt) => ctxt.get_pixel_format(),
+            HeadlessContext::EglPbuffer(ref ctxt) => ctxt.get_pixel_format(),
+            HeadlessContext::EglSurfaceless(ref ctxt) => ctxt.get_pixel_format(),
+            HeadlessContext::GlxPbuffer(ref ctxt) => ctxt.context.get_pixel_format(),
+        }
+    }
+
+    #[inline]
+    pub fn get_context_info(&self) -> ContextInfo {
+        match *self {
+            HeadlessContext::OsMesa(ref ctxt) => ctxt.get_context_info(),
+            HeadlessContext::EglPbuffer(ref ctxt) => ctxt.get_context_info(),
+            HeadlessContext::EglSurfaceless(ref ctxt) => ctxt.get_context_info(),
+            HeadlessContext::GlxPbuffer(ref ctxt) => ctxt.context.get_context_info(),
+        }
+    }
+
+    #[inline]
+    pub fn get_platform_extensions(&self) -> &Extensions {
+        match *self {
+            HeadlessContext::OsMesa(ref ctxt) => ctxt.get_extensions(),
+            HeadlessContext::EglPbuffer(ref ctxt) => ctxt.get_extensions(),
+            HeadlessContext::EglSurfaceless(ref ctxt) => ctxt.get_platform_extensions(),
+            HeadlessContext::GlxPbuffer(ref ctxt) => ctxt.context.get_extensions(),
+        }
+    }
+
+    #[inline]
+    pub fn resize(&self, width: u32, height: u32) -> Result<(), CreationError> {
+        match *self {
+            HeadlessContext::OsMesa(ref ctxt) => ctxt.resize((width, height)),
+            HeadlessContext::EglPbuffer(ref ctxt) => unsafe {
+                return ctxt.resize_pbuffer((width, height));
+            },
+            HeadlessContext::EglSurfaceless(ref ctxt) => ctxt.resize(width, height),
+            HeadlessContext::GlxPbuffer(ref ctxt) => unsafe {
+                let (xlib, _) = ctxt.display.get();
+                return ctxt.context.resize_pbuffer(xlib, (width, height));
+            },
+        }
+        Ok(())
+    }
+
+    /// Returns the framebuffer object that contains the rendered image, `0` being the default
+    /// framebuffer. The context must be current.
+    #[inline]
+    pub unsafe fn resolve_framebuffer(&self) -> u32 {
+        match *self {
+            HeadlessContext::EglSurfaceless(ref ctxt) => ctxt.resolve_framebuffer(),
+            _ => 0,
+        }
+    }
+
+    #[inline]
+    pub unsafe fn raw_handle(&self) -> RawHandle {
+        match *self {
+            HeadlessContext::OsMesa(ref ctxt) => RawHandle::OsMesa(ctxt.raw_handle()),
+            HeadlessContext::EglPbuffer(ref ctxt) => RawHandle::Egl(ctxt.raw_handle()),
+            HeadlessContext::EglSurfaceless(ref ctxt) => RawHandle::Egl(ctxt.raw_handle()),
+            HeadlessContext::GlxPbuffer(ref ctxt) => RawHandle::Glx(ctxt.context.raw_handle()),
+        }
+    }
+}
+
+/// A GLX context rendering to a pbuffer.
+pub struct GlxPbufferContext {
+    // the context must be destroyed before the connection is closed
+    context: GlxContext,
+    display: XDisplayRef,
+}
+
+/// The connection to the X server used by a `GlxPbufferContext`.
+#[derive(Clone)]
+pub enum XDisplayRef {
+    /// A connection opened for headless contexts.
+    Own(Arc<XDisplay>),
+    /// The connection of winit, when sharing with the context of a window.
+    Winit(Arc<XConnection>),
+}
+
+/// A connection to the X server that is closed on drop.
+pub struct XDisplay {
+    xlib: Xlib,
+    display: *mut Display,
+}
+
+impl GlxPbufferContext {
+    fn new(glx: glx::ffi::glx::Glx, dimensions: (u32, u32), pf_reqs: &PixelFormatRequirements,
+           opengl: &GlAttributes<&GlxPbufferContext>)
+           -> Result<GlxPbufferContext, CreationError>
+    {
+        // contexts can only be shared on the same connection
+        let display = match opengl.sharing {
+            Some(ctxt) => ctxt.display.clone(),
+            None => XDisplayRef::Own(Arc::new(XDisplay::open()?)),
+        };
+
+        let opengl = opengl.clone().map_sharing(|ctxt| &ctxt.context);
+        GlxPbufferContext::with_display(glx, display, dimensions, pf_reqs, &opengl)
+    }
+
+    /// Creates a context on the given connection, which must be the one of the shared context.
+    pub fn with_display(glx: glx::ffi::glx::Glx, display: XDisplayRef, dimensions: (u32, u32),
+                        pf_reqs: &PixelFormatRequirements, opengl: &GlAttributes<&GlxContext>)
+                        -> Result<GlxPbufferContext, CreationError>
+    {
+        let context = {
+            let (xlib, raw_display) = display.get();
+            let screen_id = unsafe { (xlib.XDefaultScreen)(raw_display) };
+            GlxContext::new(glx, xlib, pf_reqs, opengl, raw_display, screen_id, false,
+                            glx::SurfaceType::PBuffer)
+                .and_then(|prototype| prototype.finish_pbuffer(dimensions))?
+        };
+
+        Ok(GlxPbufferContext {
+            context: context,
+            display: display,
+        })
+    }
+}
+
+impl XDisplayRef {
+    #[inline]
+    fn get(&self) -> (&Xlib, *mut Display) {
+        match *self {
+            XDisplayRef::Own(ref display) => (&display.xlib, display.display),
+            XDisplayRef::Winit(ref connection) => (&connection.xlib, connection.display),
+        }
+    }
+}
+
+impl XDisplay {
+    fn open() -> Result<XDisplay, CreationError> {
+        let xlib = Xlib::open().map_err(|err| CreationError::NoBackendAvailable(Box::new(err)))?;
+        let display = unsafe { (xlib.XOpenDisplay)(ptr::null()) };
+        if display.is_null() {
+            return Err(CreationError::OsError("XOpenDisplay failed".to_owned()));
+        }
+
+        Ok(XDisplay {
+            xlib: xlib,
+            display: display,
+        })
+    }
+}
+
+impl Drop for XDisplay {
+    fn drop(&mut self) {
+        unsafe { (self.xlib.XCloseDisplay)(self.display); }
+    }
+}
+
+unsafe impl Send for XDisplay {}
+unsafe impl Sync for XDisplay {}
+
+fn backends_failed(mut errors: Vec<(HeadlessBackend, CreationError)>) -> CreationError {
+    if errors.len() == 1 {
+        errors.remove(0).1
+    } else {
+        CreationError::NoBackendAvailable(Box::new(HeadlessBackendErrors(errors)))
+    }
+}
+
+/// Error returned when none of the requested backends could create a context.
+#[derive(Debug)]
+struct HeadlessBackendErrors(Vec<(HeadlessBackend, CreationError)>);
+
+impl fmt::Display for HeadlessBackendErrors {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("every headless backend failed")?;
+        for &(backend, ref err) in &self.0 {
+            write!(f, "; {:?}: {}", backend, err)?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for HeadlessBackendErrors {
+    fn description(&self) -> &str {
+        "every headless backend failed"
+    }
+}
+
+/// Error returned when the library of a backend could not be loaded.
+#[derive(Debug)]
+struct LibraryNotFound(&'static str);
+
+impl fmt::Display for LibraryNotFound {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} could not be loaded", self.0)
+    }
+}
+
+impl error::Error for LibraryNotFound {
+    fn description(&self) -> &str {
+        "a library could not be loaded"
+    }
+}
+
+#[inline]
+fn library_not_found(name: &'static str) -> CreationError {
+    CreationError::NoBackendAvailable(Box::new(LibraryNotFound(name)))
+}
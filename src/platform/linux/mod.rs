@@ -1,16 +1,21 @@
 #![cfg(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd", target_os = "openbsd"))]
 
-use {Api, ContextError, CreationError, GlAttributes, PixelFormat, PixelFormatRequirements};
-use api::egl;
-use api::glx;
-use api::osmesa::OsMesaContext;
+pub use self::headless::{HeadlessBackend, HeadlessContext, PlatformSpecificHeadlessBuilderAttributes};
+
+use {ConfigDescriptor, ContextError, CreationError, GlAttributes, PixelFormat};
+use {ContextInfo, Extensions, PixelFormatRequirements};
+use api::{dlopen, egl, glx, osmesa};
+use api::egl::ffi::egl::Egl;
+use api::glx::ffi::glx::Glx;
 use self::x11::GlContext;
 
 use winit;
 use winit::os::unix::EventsLoopExt;
 
-use std::os::raw::c_void;
+use std::ffi::CString;
 
+mod headless;
+mod surfaceless;
 mod wayland;
 mod x11;
 
@@ -19,6 +24,7 @@ mod x11;
 pub enum RawHandle {
     Glx(glx::ffi::GLXContext),
     Egl(egl::ffi::EGLContext),
+    OsMesa(osmesa::ffi::OSMesaContext),
 }
 
 pub enum Context {
@@ -26,6 +32,18 @@ pub enum Context {
     Wayland(wayland::Context)
 }
 
+/// See the docs in the crate root file.
+#[inline]
+pub fn available_configs(events_loop: &winit::EventsLoop)
+                         -> Result<Vec<ConfigDescriptor>, CreationError>
+{
+    if events_loop.is_wayland() {
+        wayland::available_configs()
+    } else {
+        x11::available_configs(events_loop)
+    }
+}
+
 impl Context {
     #[inline]
     pub fn new(
@@ -60,6 +78,23 @@ impl Context {
         }
     }
 
+    /// Creates a new context for `window`, the window of this context, with the same pixel
+    /// format. `gl_attr.sharing` must be `None`.
+    pub fn recreate(&self, window: &winit::Window, gl_attr: &GlAttributes<&Context>)
+                    -> Result<Self, CreationError>
+    {
+        match *self {
+            Context::X(ref ctxt) => {
+                let gl_attr = gl_attr.clone().map_sharing(|_| unreachable!());
+                ctxt.recreate(window, &gl_attr).map(Context::X)
+            },
+            Context::Wayland(ref ctxt) => {
+                let gl_attr = gl_attr.clone().map_sharing(|_| unreachable!());
+                ctxt.recreate(&gl_attr).map(Context::Wayland)
+            },
+        }
+    }
+
     pub fn resize(&self, width: u32, height: u32) {
         match *self {
             Context::X(ref _ctxt) => (),
@@ -116,72 +151,92 @@ impl Context {
     }
 
     #[inline]
-    pub unsafe fn raw_handle(&self) -> RawHandle {
+    pub fn get_context_info(&self) -> ContextInfo {
         match *self {
-            Context::X(ref ctxt) => match *ctxt.raw_handle() {
-                GlContext::Glx(ref ctxt) => RawHandle::Glx(ctxt.raw_handle()),
-                GlContext::Egl(ref ctxt) => RawHandle::Egl(ctxt.raw_handle()),
-                GlContext::None => panic!()
-            },
-            Context::Wayland(ref ctxt) => RawHandle::Egl(ctxt.raw_handle())
+            Context::X(ref ctxt) => ctxt.get_context_info(),
+            Context::Wayland(ref ctxt) => ctxt.get_context_info()
         }
     }
-}
-
-#[derive(Clone, Default)]
-pub struct PlatformSpecificHeadlessBuilderAttributes;
-
-pub struct HeadlessContext(OsMesaContext);
-
-impl HeadlessContext {
-    fn from(mesa: OsMesaContext) -> Self {
-        HeadlessContext(mesa)
-    }
-}
-
-impl HeadlessContext {
-    pub fn new(dimensions: (u32, u32), pf_reqs: &PixelFormatRequirements,
-               opengl: &GlAttributes<&HeadlessContext>,
-               _: &PlatformSpecificHeadlessBuilderAttributes)
-               -> Result<HeadlessContext, CreationError>
-    {
-        let opengl = opengl.clone().map_sharing(|c| &c.0);
-
-        OsMesaContext::new(dimensions, pf_reqs, &opengl).map(HeadlessContext::from)
-    }
-
-    #[inline]
-    pub unsafe fn make_current(&self) -> Result<(), ContextError> {
-        self.0.make_current()
-    }
-
-    #[inline]
-    pub fn is_current(&self) -> bool {
-        self.0.is_current()
-    }
 
     #[inline]
-    pub fn get_proc_address(&self, addr: &str) -> *const () {
-        self.0.get_proc_address(addr)
+    pub fn get_platform_extensions(&self) -> &Extensions {
+        match *self {
+            Context::X(ref ctxt) => ctxt.get_platform_extensions(),
+            Context::Wayland(ref ctxt) => ctxt.get_platform_extensions()
+        }
     }
 
+    /// Returns whether vsync is enabled, or `None` if it is unknown.
     #[inline]
-    pub fn swap_buffers(&self) -> Result<(), ContextError> {
-        self.0.swap_buffers()
+    pub fn get_vsync(&self) -> Option<bool> {
+        match *self {
+            Context::X(ref ctxt) => ctxt.get_vsync(),
+            Context::Wayland(ref ctxt) => ctxt.get_vsync(),
+        }
     }
 
     #[inline]
-    pub fn get_api(&self) -> Api {
-        self.0.get_api()
+    pub unsafe fn raw_handle(&self) -> RawHandle {
+        match *self {
+            Context::X(ref ctxt) => match *ctxt.raw_handle() {
+                GlContext::Glx(ref ctxt) => RawHandle::Glx(ctxt.raw_handle()),
+                GlContext::Egl(ref ctxt) => RawHandle::Egl(ctxt.raw_handle()),
+                GlContext::None => panic!()
+            },
+            Context::Wayland(ref ctxt) => RawHandle::Egl(ctxt.raw_handle())
+        }
     }
+}
 
-    #[inline]
-    pub fn get_pixel_format(&self) -> PixelFormat {
-        self.0.get_pixel_format()
-    }
+/// The GLX and EGL libraries available on the system, if any.
+pub struct GlxOrEgl {
+    pub glx: Option<Glx>,
+    pub egl: Option<Egl>,
+}
 
-    #[inline]
-    pub unsafe fn raw_handle(&self) -> *mut c_void {
-        self.0.raw_handle()
+impl GlxOrEgl {
+    pub fn new() -> GlxOrEgl {
+        // TODO: use something safer than raw "dlopen"
+        let glx = {
+            let mut libglx = unsafe {
+                dlopen::dlopen(b"libGL.so.1\0".as_ptr() as *const _, dlopen::RTLD_NOW)
+            };
+            if libglx.is_null() {
+                libglx = unsafe {
+                    dlopen::dlopen(b"libGL.so\0".as_ptr() as *const _, dlopen::RTLD_NOW)
+                };
+            }
+            if libglx.is_null() {
+                None
+            } else {
+                Some(Glx::load_with(|sym| {
+                    let sym = CString::new(sym).unwrap();
+                    unsafe { dlopen::dlsym(libglx, sym.as_ptr()) }
+                }))
+            }
+        };
+        // TODO: use something safer than raw "dlopen"
+        let egl = {
+            let mut libegl = unsafe {
+                dlopen::dlopen(b"libEGL.so.1\0".as_ptr() as *const _, dlopen::RTLD_NOW)
+            };
+            if libegl.is_null() {
+                libegl = unsafe {
+                    dlopen::dlopen(b"libEGL.so\0".as_ptr() as *const _, dlopen::RTLD_NOW)
+                };
+            }
+            if libegl.is_null() {
+                None
+            } else {
+                Some(Egl::load_with(|sym| {
+                    let sym = CString::new(sym).unwrap();
+                    unsafe { dlopen::dlsym(libegl, sym.as_ptr()) }
+                }))
+            }
+        };
+        GlxOrEgl {
+            glx: glx,
+            egl: egl,
+        }
     }
 }
@@ -0,0 +1,428 @@
+use {Api, ContextError, ContextInfo, CreationError, Extensions, GlAttributes, PixelFormat};
+use PixelFormatRequirements;
+use api::egl::{self, ffi, Context as EglContext};
+use api::egl::ffi::egl::Egl;
+use api::gl;
+use api::gl::types::{GLenum, GLint, GLsizei, GLuint};
+
+use std::sync::Mutex;
+
+/// An EGL context without any surface, rendering into a framebuffer object.
+///
+/// Requires `EGL_MESA_platform_surfaceless` and `EGL_KHR_surfaceless_context`, which are
+/// available with Mesa even without any GPU (llvmpipe).
+pub struct SurfacelessContext {
+    context: EglContext,
+    gl: gl::Gl,
+    framebuffer: Framebuffer,
+    // not a `Cell`, as the context can be resized from any thread
+    size: Mutex<Size>,
+    pixel_format: PixelFormat,
+}
+
+struct Size {
+    dimensions: (u32, u32),
+    // the renderbuffers can only be reallocated while the context is current
+    resize_pending: bool,
+}
+
+/// The framebuffer object that replaces the default framebuffer of the context.
+struct Framebuffer {
+    id: GLuint,
+    attachments: Vec<Attachment>,
+    samples: GLsizei,
+    /// Single-sampled framebuffer that multisampled content is resolved into before reading it.
+    resolve_target: Option<(GLuint, Attachment)>,
+}
+
+struct Attachment {
+    renderbuffer: GLuint,
+    format: GLenum,
+    attachment_point: GLenum,
+}
+
+impl SurfacelessContext {
+    /// Creates a context on `native_display`, which is usually `NativeDisplay::Surfaceless`. The
+    /// shared context must be on the same display.
+    pub fn new(
+        egl: Egl,
+        native_display: egl::NativeDisplay,
+        dimensions: (u32, u32),
+        pf_reqs: &PixelFormatRequirements,
+        opengl: &GlAttributes<&EglContext>,
+    ) -> Result<SurfacelessContext, CreationError>
+    {
+        // framebuffer objects don't have accumulation or auxiliary buffers
+        if pf_reqs.accum_bits.unwrap_or(0) != 0 || pf_reqs.aux_buffers.unwrap_or(0) != 0 {
+            return Err(CreationError::NoAvailablePixelFormat);
+        }
+
+        // the buffers live in our own framebuffer object, so only the requirements that affect
+        // the context itself are passed to the config chooser
+        let config_reqs = PixelFormatRequirements {
+            color_bits: None,
+            channel_bits: None,
+            float_color_buffer: false,
+            alpha_bits: None,
+            depth_bits: None,
+            stencil_bits: None,
+            multisampling: None,
+            srgb: false,
+            .. pf_reqs.clone()
+        };
+
+        let context = EglContext::new(egl, &config_reqs, opengl, native_display,
+                                      egl::SurfaceType::Surfaceless)
+            .and_then(|p| p.finish_surfaceless())?;
+
+        unsafe {
+            context.make_current()
+                .map_err(|_| CreationError::OsError("eglMakeCurrent failed".to_owned()))?;
+        }
+
+        // a chosen configuration describes the buffers to allocate
+        let pf_reqs = match pf_reqs.config_id {
+            Some(_) => {
+                let format = context.get_pixel_format();
+                PixelFormatRequirements {
+                    channel_bits: Some((format.red_bits, format.green_bits, format.blue_bits)),
+                    float_color_buffer: format.float_color_buffer,
+                    alpha_bits: Some(format.alpha_bits),
+                    depth_bits: Some(format.depth_bits),
+                    stencil_bits: Some(format.stencil_bits),
+                    multisampling: Some(format.multisampling.unwrap_or(0)),
+                    .. pf_reqs.clone()
+                }
+            },
+            None => pf_reqs.clone(),
+        };
+        let pf_reqs = &pf_reqs;
+
+        let gl = gl::Gl::load_with(|symbol| context.get_proc_address(symbol) as *const _);
+        let (color_format, (red_bits, green_bits, blue_bits), alpha_bits) =
+            color_format(context.get_api(), pf_reqs)?;
+        let (depth_stencil, depth_bits, stencil_bits) = depth_stencil_format(pf_reqs)?;
+
+        let samples = match pf_reqs.multisampling {
+            None | Some(0) => 0,
+            Some(samples) => samples as GLsizei,
+        };
+
+        let framebuffer = unsafe {
+            Framebuffer::new(&gl, color_format, depth_stencil, samples, dimensions)?
+        };
+
+        let pixel_format = PixelFormat {
+            hardware_accelerated: context.get_pixel_format().hardware_accelerated,
+            color_bits: red_bits + green_bits + blue_bits,
+            red_bits: red_bits,
+            green_bits: green_bits,
+            blue_bits: blue_bits,
+            alpha_bits: alpha_bits,
+            depth_bits: depth_bits,
+            stencil_bits: stencil_bits,
+            accum_bits: 0,
+            aux_buffers: 0,
+            stereoscopy: false,
+            double_buffer: false,
+            multisampling: match unsafe { framebuffer.get_samples(&gl) } {
+                0 => None,
+                samples => Some(samples as u16),
+            },
+            srgb: color_format == gl::SRGB8_ALPHA8,
+            float_color_buffer: pf_reqs.float_color_buffer,
+            transparent: false,
+            release_behavior: context.get_pixel_format().release_behavior,
+            native_visual_id: None,
+            config_id: context.get_pixel_format().config_id,
+        };
+
+        Ok(SurfacelessContext {
+            context: context,
+            gl: gl,
+            framebuffer: framebuffer,
+            size: Mutex::new(Size { dimensions: dimensions, resize_pending: false }),
+            pixel_format: pixel_format,
+        })
+    }
+
+    #[inline]
+    pub unsafe fn make_current(&self) -> Result<(), ContextError> {
+        self.context.make_current()?;
+        let mut size = self.size.lock().unwrap();
+        if size.resize_pending {
+            self.framebuffer.allocate(&self.gl, size.dimensions);
+            size.resize_pending = false;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn is_current(&self) -> bool {
+        self.context.is_current()
+    }
+
+    #[inline]
+    pub fn get_proc_address(&self, addr: &str) -> *const () {
+        self.context.get_proc_address(addr)
+    }
+
+    #[inline]
+    pub fn swap_buffers(&self) -> Result<(), ContextError> {
+        // there is nothing to swap, the framebuffer object is always the one being rendered to
+        Ok(())
+    }
+
+    #[inline]
+    pub fn get_api(&self) -> Api {
+        self.context.get_api()
+    }
+
+    #[inline]
+    pub fn get_pixel_format(&self) -> PixelFormat {
+        self.pixel_format.clone()
+    }
+
+    #[inline]
+    pub fn get_context_info(&self) -> ContextInfo {
+        self.context.get_context_info()
+    }
+
+    #[inline]
+    pub fn get_platform_extensions(&self) -> &Extensions {
+        self.context.get_extensions()
+    }
+
+    #[inline]
+    pub unsafe fn raw_handle(&self) -> ffi::EGLContext {
+        self.context.raw_handle()
+    }
+
+    /// Returns the framebuffer object that contains the rendered image, resolving it first if it
+    /// is multisampled. The context must be current.
+    #[inline]
+    pub unsafe fn resolve_framebuffer(&self) -> GLuint {
+        self.framebuffer.resolve(&self.gl, self.size.lock().unwrap().dimensions)
+    }
+
+    /// Returns the underlying EGL context.
+    #[inline]
+    pub fn egl_context(&self) -> &EglContext {
+        &self.context
+    }
+
+    /// Reallocates the renderbuffers with the given dimensions. If the context isn't current,
+    /// this is done the next time it is made current.
+    ///
+    /// The content of the renderbuffers is lost.
+    pub fn resize(&self, width: u32, height: u32) {
+        let mut size = self.size.lock().unwrap();
+        size.dimensions = (width, height);
+        if self.is_current() {
+            unsafe { self.framebuffer.allocate(&self.gl, (width, height)); }
+            size.resize_pending = false;
+        } else {
+            size.resize_pending = true;
+        }
+    }
+}
+
+unsafe impl Send for SurfacelessContext {}
+unsafe impl Sync for SurfacelessContext {}
+
+impl Framebuffer {
+    /// Creates the framebuffer object and binds it. The context must be current.
+    unsafe fn new(gl: &gl::Gl, color_format: GLenum, depth_stencil: Option<(GLenum, GLenum)>,
+                  samples: GLsizei, dimensions: (u32, u32))
+                  -> Result<Framebuffer, CreationError>
+    {
+        if samples > 0 {
+            let mut max_samples = 0;
+            gl.GetIntegerv(gl::MAX_SAMPLES, &mut max_samples);
+            if samples > max_samples {
+                return Err(CreationError::NoAvailablePixelFormat);
+            }
+        }
+
+        let mut id = 0;
+        gl.GenFramebuffers(1, &mut id);
+        gl.BindFramebuffer(gl::FRAMEBUFFER, id);
+
+        let mut attachments = vec![(color_format, gl::COLOR_ATTACHMENT0)];
+        attachments.extend(depth_stencil);
+
+        let attachments = attachments.into_iter().map(|(format, attachment_point)| {
+            Attachment::new(gl, format, attachment_point)
+        }).collect();
+
+        // `glReadPixels` can't read from a multisampled framebuffer
+        let resolve = if samples > 0 {
+            let mut resolve_id = 0;
+            gl.GenFramebuffers(1, &mut resolve_id);
+            Some((resolve_id, Attachment::new(gl, color_format, gl::COLOR_ATTACHMENT0)))
+        } else {
+            None
+        };
+
+        let framebuffer = Framebuffer {
+            id: id,
+            attachments: attachments,
+            samples: samples,
+            resolve_target: resolve,
+        };
+
+        framebuffer.allocate(gl, dimensions);
+
+        // without a surface, the viewport is not initialized by `eglMakeCurrent`
+        gl.Viewport(0, 0, dimensions.0 as GLsizei, dimensions.1 as GLsizei);
+
+        if let Some((resolve_id, ref attachment)) = framebuffer.resolve_target {
+            gl.BindFramebuffer(gl::FRAMEBUFFER, resolve_id);
+            gl.FramebufferRenderbuffer(gl::FRAMEBUFFER, attachment.attachment_point,
+                                       gl::RENDERBUFFER, attachment.renderbuffer);
+            check_status(gl)?;
+            gl.BindFramebuffer(gl::FRAMEBUFFER, id);
+        }
+
+        for attachment in &framebuffer.attachments {
+            gl.FramebufferRenderbuffer(gl::FRAMEBUFFER, attachment.attachment_point,
+                                       gl::RENDERBUFFER, attachment.renderbuffer);
+        }
+        check_status(gl)?;
+
+        Ok(framebuffer)
+    }
+
+    /// Allocates the storage of every attachment. The context must be current.
+    unsafe fn allocate(&self, gl: &gl::Gl, (width, height): (u32, u32)) {
+        let mut previous_renderbuffer = 0;
+        gl.GetIntegerv(gl::RENDERBUFFER_BINDING, &mut previous_renderbuffer);
+
+        for attachment in &self.attachments {
+            gl.BindRenderbuffer(gl::RENDERBUFFER, attachment.renderbuffer);
+            if self.samples > 0 {
+                gl.RenderbufferStorageMultisample(gl::RENDERBUFFER, self.samples,
+                                                  attachment.format, width as GLsizei,
+                                                  height as GLsizei);
+            } else {
+                gl.RenderbufferStorage(gl::RENDERBUFFER, attachment.format, width as GLsizei,
+                                       height as GLsizei);
+            }
+        }
+        if let Some((_, ref attachment)) = self.resolve_target {
+            gl.BindRenderbuffer(gl::RENDERBUFFER, attachment.renderbuffer);
+            gl.RenderbufferStorage(gl::RENDERBUFFER, attachment.format, width as GLsizei,
+                                   height as GLsizei);
+        }
+        gl.BindRenderbuffer(gl::RENDERBUFFER, previous_renderbuffer as GLuint);
+    }
+
+    /// Returns the number of samples that the driver actually allocated.
+    unsafe fn get_samples(&self, gl: &gl::Gl) -> GLint {
+        let mut samples = 0;
+        gl.BindRenderbuffer(gl::RENDERBUFFER, self.attachments[0].renderbuffer);
+        gl.GetRenderbufferParameteriv(gl::RENDERBUFFER, gl::RENDERBUFFER_SAMPLES, &mut samples);
+        gl.BindRenderbuffer(gl::RENDERBUFFER, 0);
+        samples
+    }
+
+    /// Returns the framebuffer object to read the rendered image from. If multisampling is
+    /// enabled, the image is first resolved into a single-sampled framebuffer.
+    ///
+    /// The context must be current. The framebuffer bindings are left untouched.
+    unsafe fn resolve(&self, gl: &gl::Gl, (width, height): (u32, u32)) -> GLuint {
+        let resolve_id = match self.resolve_target {
+            Some((resolve_id, _)) => resolve_id,
+            None => return self.id,
+        };
+
+        let (mut previous_read, mut previous_draw) = (0, 0);
+        gl.GetIntegerv(gl::READ_FRAMEBUFFER_BINDING, &mut previous_read);
+        gl.GetIntegerv(gl::DRAW_FRAMEBUFFER_BINDING, &mut previous_draw);
+
+        gl.BindFramebuffer(gl::READ_FRAMEBUFFER, self.id);
+        gl.BindFramebuffer(gl::DRAW_FRAMEBUFFER, resolve_id);
+        gl.BlitFramebuffer(0, 0, width as GLint, height as GLint, 0, 0, width as GLint,
+                           height as GLint, gl::COLOR_BUFFER_BIT, gl::NEAREST);
+
+        gl.BindFramebuffer(gl::READ_FRAMEBUFFER, previous_read as GLuint);
+        gl.BindFramebuffer(gl::DRAW_FRAMEBUFFER, previous_draw as GLuint);
+        resolve_id
+    }
+}
+
+impl Attachment {
+    /// Generates the renderbuffer of the attachment. Its storage is allocated by
+    /// `Framebuffer::allocate`.
+    unsafe fn new(gl: &gl::Gl, format: GLenum, attachment_point: GLenum) -> Attachment {
+        let mut renderbuffer = 0;
+        gl.GenRenderbuffers(1, &mut renderbuffer);
+        Attachment {
+            renderbuffer: renderbuffer,
+            format: format,
+            attachment_point: attachment_point,
+        }
+    }
+}
+
+/// Checks the completeness of the framebuffer object bound to `GL_FRAMEBUFFER`.
+unsafe fn check_status(gl: &gl::Gl) -> Result<(), CreationError> {
+    let status = gl.CheckFramebufferStatus(gl::FRAMEBUFFER);
+    if status != gl::FRAMEBUFFER_COMPLETE {
+        return Err(CreationError::OsError(format!("The offscreen framebuffer is incomplete \
+                                                   (status 0x{:x})", status)));
+    }
+    Ok(())
+}
+
+/// Chooses the internal format of the color renderbuffer.
+///
+/// Returns the format along with its number of red, green, blue and alpha bits.
+fn color_format(api: Api, reqs: &PixelFormatRequirements)
+                -> Result<(GLenum, (u8, u8, u8), u8), CreationError>
+{
+    let (red, green, blue) = reqs.rgb_bits().unwrap_or((8, 8, 8));
+    let alpha = reqs.alpha_bits.unwrap_or(8);
+
+    // the smallest formats first, the first one that is large enough is chosen
+    let formats = [
+        (true, false, gl::RGBA16F, (16, 16, 16), 16),
+        (true, false, gl::RGBA32F, (32, 32, 32), 32),
+        (false, true, gl::SRGB8_ALPHA8, (8, 8, 8), 8),
+        (false, false, gl::RGB565, (5, 6, 5), 0),
+        (false, false, gl::RGB8, (8, 8, 8), 0),
+        (false, false, gl::RGBA8, (8, 8, 8), 8),
+        (false, false, gl::RGB10_A2, (10, 10, 10), 2),
+        (false, false, gl::RGBA16, (16, 16, 16), 16),
+    ];
+
+    formats.iter()
+           .filter(|&&(float, srgb, format, _, _)| {
+               float == reqs.float_color_buffer && srgb == reqs.srgb &&
+               // RGBA16 is not color-renderable in OpenGL ES
+               (format != gl::RGBA16 || api == Api::OpenGl)
+           })
+           .find(|&&(_, _, _, (r, g, b), a)| r >= red && g >= green && b >= blue && a >= alpha)
+           .map(|&(_, _, format, channels, a)| (format, channels, a))
+           .ok_or(CreationError::NoAvailablePixelFormat)
+}
+
+/// Chooses the internal format and attachment point of the depth/stencil renderbuffer, if any.
+///
+/// Returns them along with the number of depth and stencil bits.
+fn depth_stencil_format(reqs: &PixelFormatRequirements)
+                        -> Result<(Option<(GLenum, GLenum)>, u8, u8), CreationError>
+{
+    let depth = reqs.depth_bits.unwrap_or(0);
+    let stencil = reqs.stencil_bits.unwrap_or(0);
+
+    Ok(match (depth, stencil) {
+        (0, 0) => (None, 0, 0),
+        (0...24, 1...8) => (Some((gl::DEPTH24_STENCIL8, gl::DEPTH_STENCIL_ATTACHMENT)), 24, 8),
+        (25...32, 1...8) => (Some((gl::DEPTH32F_STENCIL8, gl::DEPTH_STENCIL_ATTACHMENT)), 32, 8),
+        (1...16, 0) => (Some((gl::DEPTH_COMPONENT16, gl::DEPTH_ATTACHMENT)), 16, 0),
+        (17...24, 0) => (Some((gl::DEPTH_COMPONENT24, gl::DEPTH_ATTACHMENT)), 24, 0),
+        (25...32, 0) => (Some((gl::DEPTH_COMPONENT32F, gl::DEPTH_ATTACHMENT)), 32, 0),
+        _ => return Err(CreationError::NoAvailablePixelFormat),
+    })
+}
@@ -2,16 +2,29 @@ use std::sync::Arc;
 use std::ffi::CString;
 use winit;
 use winit::os::unix::WindowExt;
-use {ContextError, CreationError, GlAttributes, PixelFormat, PixelFormatRequirements};
+use {ConfigDescriptor, ContextError, CreationError, GlAttributes, PixelFormat};
+use {ContextInfo, Extensions, PixelFormatRequirements};
 use api::dlopen;
 use api::egl::{self, ffi, Context as EglContext};
 use wayland_client::egl as wegl;
 
+use super::GlxOrEgl;
+use super::headless::HeadlessContext;
+use super::surfaceless::SurfacelessContext;
+
 pub struct Context {
     egl_surface: Arc<wegl::WlEglSurface>,
     context: EglContext,
 }
 
+/// Lists the EGL configurations of the compositor.
+pub fn available_configs() -> Result<Vec<ConfigDescriptor>, CreationError> {
+    let egl = GlxOrEgl::new().egl.ok_or(CreationError::NotSupported)?;
+    // the events loop doesn't expose its connection, so the configurations are queried on the
+    // default one, which has the same driver
+    egl::available_configs(&egl, egl::NativeDisplay::Wayland(None))
+}
+
 impl Context {
     pub fn new(
         window_builder: winit::WindowBuilder,
@@ -36,11 +49,12 @@ impl Context {
                 let sym = CString::new(sym).unwrap();
                 unsafe { dlopen::dlsym(libegl, sym.as_ptr()) }
             });
-            let gl_attr = gl_attr.clone().map_sharing(|_| unimplemented!()); // TODO
+            // the shared context must use the same `EGLDisplay`, which `EglContext::new` checks
+            let gl_attr = gl_attr.clone().map_sharing(|ctxt| &ctxt.context);
             let native_display = egl::NativeDisplay::Wayland(Some(
                 window.get_wayland_display().unwrap() as *const _
             ));
-            EglContext::new(egl, pf_reqs, &gl_attr, native_display)
+            EglContext::new(egl, pf_reqs, &gl_attr, native_display, egl::SurfaceType::Window)
                 .and_then(|p| p.finish(egl_surface.ptr() as *const _))?
         };
         let context = Context {
@@ -50,6 +64,32 @@ impl Context {
         Ok((window, context))
     }
 
+    /// Creates a surfaceless context that shares its objects with this one, on the same display.
+    /// `opengl.sharing` must be `self`.
+    pub fn new_shared_headless(&self, window: &winit::Window, dimensions: (u32, u32),
+                               pf_reqs: &PixelFormatRequirements, opengl: &GlAttributes<&Context>)
+                               -> Result<HeadlessContext, CreationError>
+    {
+        let egl = GlxOrEgl::new().egl.ok_or(CreationError::NotSupported)?;
+        let opengl = opengl.clone().map_sharing(|ctxt| &ctxt.context);
+        let native_display = egl::NativeDisplay::Wayland(Some(
+            window.get_wayland_display().unwrap() as *const _
+        ));
+        SurfacelessContext::new(egl, native_display, dimensions, pf_reqs, &opengl)
+            .map(HeadlessContext::EglSurfaceless)
+    }
+
+    /// Creates a new context that renders to the surface of this one. `gl_attr.sharing` must be
+    /// `None`.
+    pub fn recreate(&self, gl_attr: &GlAttributes<&Context>) -> Result<Self, CreationError> {
+        let gl_attr = gl_attr.clone().map_sharing(|ctxt| &ctxt.context);
+        let context = self.context.recreate(&gl_attr)?;
+        Ok(Context {
+            egl_surface: self.egl_surface.clone(),
+            context: context,
+        })
+    }
+
     pub fn resize(&self, width: u32, height: u32) {
         self.egl_surface.resize(width as i32, height as i32, 0, 0);
     }
@@ -84,6 +124,21 @@ impl Context {
         self.context.get_pixel_format().clone()
     }
 
+    #[inline]
+    pub fn get_context_info(&self) -> ContextInfo {
+        self.context.get_context_info()
+    }
+
+    #[inline]
+    pub fn get_platform_extensions(&self) -> &Extensions {
+        self.context.get_extensions()
+    }
+
+    #[inline]
+    pub fn get_vsync(&self) -> Option<bool> {
+        None
+    }
+
     #[inline]
     pub unsafe fn raw_handle(&self) -> ffi::EGLContext {
         self.context.raw_handle()
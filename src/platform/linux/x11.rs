@@ -6,15 +6,14 @@ use std::sync::Arc;
 use winit;
 use winit::os::unix::{EventsLoopExt, WindowExt, WindowBuilderExt};
 
-use {Api, ContextError, CreationError, GlAttributes, GlRequest, PixelFormat, PixelFormatRequirements};
-
-use std::ffi::CString;
+use {Api, ConfigBackend, ConfigDescriptor, ContextError, CreationError, GlAttributes, GlRequest};
+use {ContextInfo, Extensions, PixelFormat, PixelFormatRequirements};
 
 use api::glx::{ffi, Context as GlxContext};
-use api::{dlopen, egl};
+use api::egl;
 use api::egl::Context as EglContext;
-use api::glx::ffi::glx::Glx;
-use api::egl::ffi::egl::Egl;
+use super::GlxOrEgl;
+use super::headless::{GlxPbufferContext, HeadlessContext, XDisplayRef};
 
 #[derive(Debug)]
 struct NoX11Connection;
@@ -31,58 +30,6 @@ impl fmt::Display for NoX11Connection {
     }
 }
 
-struct GlxOrEgl {
-    glx: Option<Glx>,
-    egl: Option<Egl>,
-}
-
-impl GlxOrEgl {
-    fn new() -> GlxOrEgl {
-        // TODO: use something safer than raw "dlopen"
-        let glx = {
-            let mut libglx = unsafe {
-                dlopen::dlopen(b"libGL.so.1\0".as_ptr() as *const _, dlopen::RTLD_NOW)
-            };
-            if libglx.is_null() {
-                libglx = unsafe {
-                    dlopen::dlopen(b"libGL.so\0".as_ptr() as *const _, dlopen::RTLD_NOW)
-                };
-            }
-            if libglx.is_null() {
-                None
-            } else {
-                Some(Glx::load_with(|sym| {
-                    let sym = CString::new(sym).unwrap();
-                    unsafe { dlopen::dlsym(libglx, sym.as_ptr()) }
-                }))
-            }
-        };
-        // TODO: use something safer than raw "dlopen"
-        let egl = {
-            let mut libegl = unsafe {
-                dlopen::dlopen(b"libEGL.so.1\0".as_ptr() as *const _, dlopen::RTLD_NOW)
-            };
-            if libegl.is_null() {
-                libegl = unsafe {
-                    dlopen::dlopen(b"libEGL.so\0".as_ptr() as *const _, dlopen::RTLD_NOW)
-                };
-            }
-            if libegl.is_null() {
-                None
-            } else {
-                Some(Egl::load_with(|sym| {
-                    let sym = CString::new(sym).unwrap();
-                    unsafe { dlopen::dlsym(libegl, sym.as_ptr()) }
-                }))
-            }
-        };
-        GlxOrEgl {
-            glx: glx,
-            egl: egl,
-        }
-    }
-}
-
 pub enum GlContext {
     Glx(GlxContext),
     Egl(EglContext),
@@ -98,6 +45,40 @@ pub struct Context {
 unsafe impl Send for Context {}
 unsafe impl Sync for Context {}
 
+/// Lists the GLX and the EGL configurations of the connection of `events_loop`.
+pub fn available_configs(events_loop: &winit::EventsLoop)
+                         -> Result<Vec<ConfigDescriptor>, CreationError>
+{
+    let display = match events_loop.get_xlib_xconnection() {
+        Some(display) => display,
+        None => return Err(CreationError::NoBackendAvailable(Box::new(NoX11Connection))),
+    };
+    let screen_id = unsafe { (display.xlib.XDefaultScreen)(display.display) };
+
+    let backend = GlxOrEgl::new();
+    if backend.glx.is_none() && backend.egl.is_none() {
+        return Err(CreationError::NotSupported);
+    }
+
+    let mut configs = Vec::new();
+    if let Some(ref glx) = backend.glx {
+        configs.extend(unsafe {
+            ::api::glx::available_configs(glx, &display.xlib, display.display, screen_id)
+        });
+    }
+    if let Some(ref egl) = backend.egl {
+        let native_display = egl::NativeDisplay::X11(Some(display.display as *const _));
+        match egl::available_configs(egl, native_display) {
+            Ok(egl_configs) => configs.extend(egl_configs),
+            // EGL may not work on this connection while GLX does
+            Err(_) if !configs.is_empty() => (),
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(configs)
+}
+
 impl Drop for Context {
     fn drop(&mut self) {
         unsafe {
@@ -133,8 +114,17 @@ impl Context {
             Egl(::api::egl::ContextPrototype<'a>),
         }
 
-        let builder_clone_opengl_glx = gl_attr.clone().map_sharing(|_| unimplemented!());      // FIXME:
-        let builder_clone_opengl_egl = gl_attr.clone().map_sharing(|_| unimplemented!());      // FIXME:
+        // a context can only share its objects with a context of the same backend
+        let shares_with_egl = match gl_attr.sharing {
+            Some(&Context { context: GlContext::Egl(_), .. }) => true,
+            _ => false,
+        };
+        let egl_config = match pf_reqs.config_id {
+            Some(id) => id.backend != ConfigBackend::Glx,
+            None => false,
+        };
+        let builder_clone_opengl_glx;
+        let builder_clone_opengl_egl;
         let backend = GlxOrEgl::new();
         let context = match gl_attr.version {
             GlRequest::Latest |
@@ -142,35 +132,50 @@ impl Context {
             GlRequest::GlThenGles { .. } => {
                 // GLX should be preferred over EGL, otherwise crashes may occur
                 // on X11 – issue #314
-                if let Some(ref glx) = backend.glx {
-                    Prototype::Glx(try!(GlxContext::new(
-                        glx.clone(),
-                        &display.xlib,
-                        pf_reqs,
-                        &builder_clone_opengl_glx,
-                        display.display,
-                        screen_id,
-                        window_builder.window.transparent,
-                    )))
-                } else if let Some(ref egl) = backend.egl {
-                    let native_display = egl::NativeDisplay::X11(Some(display.display as *const _));
-                    Prototype::Egl(try!(EglContext::new(
-                        egl.clone(),
-                        pf_reqs,
-                        &builder_clone_opengl_egl,
-                        native_display,
-                    )))
-                } else {
-                    return Err(CreationError::NotSupported);
+                // EGL is still used when sharing with an EGL context, as GLX can't share with it,
+                // and when an EGL configuration was chosen
+                match (&backend.glx, &backend.egl) {
+                    (&Some(ref glx), _) if !shares_with_egl && !egl_config => {
+                        builder_clone_opengl_glx = gl_attr.clone().map_sharing(glx_context);
+                        Prototype::Glx(try!(GlxContext::new(
+                            glx.clone(),
+                            &display.xlib,
+                            pf_reqs,
+                            &builder_clone_opengl_glx,
+                            display.display,
+                            screen_id,
+                            window_builder.window.transparent,
+                            ::api::glx::SurfaceType::Window,
+                        )))
+                    },
+                    (_, &Some(ref egl)) => {
+                        builder_clone_opengl_egl = try!(map_sharing_egl(gl_attr));
+                        let native_display = egl::NativeDisplay::X11(Some(display.display as *const _));
+                        Prototype::Egl(try!(EglContext::new(
+                            egl.clone(),
+                            pf_reqs,
+                            &builder_clone_opengl_egl,
+                            native_display,
+                            egl::SurfaceType::Window,
+                        )))
+                    },
+                    (&Some(_), &None) if shares_with_egl => {
+                        let msg = "Cannot share a GLX context with an EGL context";
+                        return Err(CreationError::PlatformSpecific(msg.into()));
+                    },
+                    (&Some(_), &None) => return Err(CreationError::NoAvailablePixelFormat),
+                    (&None, &None) => return Err(CreationError::NotSupported),
                 }
             },
             GlRequest::Specific(Api::OpenGlEs, _) => {
                 if let Some(ref egl) = backend.egl {
+                    builder_clone_opengl_egl = try!(map_sharing_egl(gl_attr));
                     Prototype::Egl(try!(EglContext::new(
                         egl.clone(),
                         pf_reqs,
                         &builder_clone_opengl_egl,
                         egl::NativeDisplay::X11(Some(display.display as *const _)),
+                        egl::SurfaceType::Window,
                     )))
                 } else {
                     return Err(CreationError::NotSupported);
@@ -243,6 +248,71 @@ impl Context {
         Ok((window, context))
     }
 
+    /// Creates a new context for `window`, the window of this context, with the same
+    /// configuration. `gl_attr.sharing` must be `None`.
+    pub fn recreate(&self, window: &winit::Window, gl_attr: &GlAttributes<&Context>)
+                    -> Result<Self, CreationError>
+    {
+        let context = match self.context {
+            GlContext::Glx(ref ctxt) => {
+                let gl_attr = gl_attr.clone().map_sharing(glx_context);
+                GlContext::Glx(ctxt.recreate(&self.display.xlib, &gl_attr)?)
+            },
+            GlContext::Egl(ref ctxt) => {
+                let gl_attr = map_sharing_egl(gl_attr)?;
+                GlContext::Egl(ctxt.recreate(&gl_attr)?)
+            },
+            GlContext::None => unreachable!(),
+        };
+
+        // creating a color map for the visual of the window, as `new` does
+        let cmap = unsafe {
+            let mut attributes: ffi::XWindowAttributes = mem::zeroed();
+            (self.display.xlib.XGetWindowAttributes)(self.display.display,
+                                                     window.get_xlib_window().unwrap(),
+                                                     &mut attributes);
+            self.display.check_errors().expect("Failed to call XGetWindowAttributes");
+
+            let cmap = (self.display.xlib.XCreateColormap)(self.display.display, attributes.root,
+                                                           attributes.visual, ffi::AllocNone);
+            self.display.check_errors().expect("Failed to call XCreateColormap");
+            cmap
+        };
+
+        Ok(Context {
+            display: self.display.clone(),
+            context: context,
+            colormap: cmap,
+        })
+    }
+
+    /// Creates a pbuffer context that shares its objects with this one, on the same connection.
+    /// `opengl.sharing` must be `self`.
+    pub fn new_shared_headless(&self, dimensions: (u32, u32), pf_reqs: &PixelFormatRequirements,
+                               opengl: &GlAttributes<&Context>)
+                               -> Result<HeadlessContext, CreationError>
+    {
+        let libraries = GlxOrEgl::new();
+        match self.context {
+            GlContext::Glx(ref ctxt) => {
+                let glx = libraries.glx.ok_or(CreationError::NotSupported)?;
+                let opengl = opengl.clone().map_sharing(|_| ctxt);
+                let display = XDisplayRef::Winit(self.display.clone());
+                GlxPbufferContext::with_display(glx, display, dimensions, pf_reqs, &opengl)
+                    .map(HeadlessContext::GlxPbuffer)
+            },
+            GlContext::Egl(ref ctxt) => {
+                let egl = libraries.egl.ok_or(CreationError::NotSupported)?;
+                let opengl = opengl.clone().map_sharing(|_| ctxt);
+                let native_display = egl::NativeDisplay::X11(Some(self.display.display as *const _));
+                EglContext::new(egl, pf_reqs, &opengl, native_display, egl::SurfaceType::PBuffer)
+                    .and_then(|prototype| prototype.finish_pbuffer(dimensions))
+                    .map(HeadlessContext::EglPbuffer)
+            },
+            GlContext::None => unreachable!(),
+        }
+    }
+
     #[inline]
     pub unsafe fn make_current(&self) -> Result<(), ContextError> {
         match self.context {
@@ -261,6 +331,14 @@ impl Context {
         }
     }
 
+    #[inline]
+    pub fn get_vsync(&self) -> Option<bool> {
+        match self.context {
+            GlContext::Glx(ref ctxt) => ctxt.get_vsync(),
+            _ => None,
+        }
+    }
+
     #[inline]
     pub fn get_proc_address(&self, addr: &str) -> *const () {
         match self.context {
@@ -297,8 +375,48 @@ impl Context {
         }
     }
 
+    #[inline]
+    pub fn get_context_info(&self) -> ContextInfo {
+        match self.context {
+            GlContext::Glx(ref ctxt) => ctxt.get_context_info(),
+            GlContext::Egl(ref ctxt) => ctxt.get_context_info(),
+            GlContext::None => panic!()
+        }
+    }
+
+    #[inline]
+    pub fn get_platform_extensions(&self) -> &Extensions {
+        match self.context {
+            GlContext::Glx(ref ctxt) => ctxt.get_extensions(),
+            GlContext::Egl(ref ctxt) => ctxt.get_extensions(),
+            GlContext::None => panic!()
+        }
+    }
+
     #[inline]
     pub unsafe fn raw_handle(&self) -> &GlContext {
         &self.context
     }
 }
+
+#[inline]
+fn glx_context(ctxt: &Context) -> &GlxContext {
+    match ctxt.context {
+        GlContext::Glx(ref ctxt) => ctxt,
+        _ => unreachable!(),
+    }
+}
+
+fn map_sharing_egl<'a>(gl_attr: &GlAttributes<&'a Context>)
+                       -> Result<GlAttributes<&'a EglContext>, CreationError>
+{
+    if let Some(&Context { context: GlContext::Glx(_), .. }) = gl_attr.sharing {
+        let msg = "Cannot share an EGL context with a GLX context";
+        return Err(CreationError::PlatformSpecific(msg.into()));
+    }
+
+    Ok(gl_attr.clone().map_sharing(|ctxt| match ctxt.context {
+        GlContext::Egl(ref ctxt) => ctxt,
+        _ => unreachable!(),
+    }))
+}
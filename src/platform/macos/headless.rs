@@ -1,9 +1,13 @@
+use ConfigDescriptor;
 use ContextError;
+use ContextInfo;
+use Extensions;
 use CreationError;
 use CreationError::OsError;
 use GlAttributes;
 use PixelFormat;
 use PixelFormatRequirements;
+use super::Context;
 use super::helpers;
 
 use core_foundation::base::TCFType;
@@ -18,30 +22,65 @@ pub struct PlatformSpecificHeadlessBuilderAttributes;
 
 pub struct HeadlessContext {
     context: id,
+    pixel_format: PixelFormat,
+    info: ContextInfo,
+    // NSOpenGL doesn't have extensions
+    extensions: Extensions,
 }
 
 impl HeadlessContext {
+    /// See the docs in the crate root file.
+    #[inline]
+    pub fn available_configs(_: &PlatformSpecificHeadlessBuilderAttributes)
+                             -> Result<Vec<ConfigDescriptor>, CreationError>
+    {
+        Err(CreationError::NotSupported)
+    }
+
     pub fn new((_width, _height): (u32, u32), pf_reqs: &PixelFormatRequirements,
                opengl: &GlAttributes<&HeadlessContext>,
                _: &PlatformSpecificHeadlessBuilderAttributes)
                -> Result<HeadlessContext, CreationError>
+    {
+        let share = opengl.sharing.map(|ctxt| ctxt.context).unwrap_or(nil);
+        HeadlessContext::with_share_context(pf_reqs, opengl, share)
+    }
+
+    /// Creates a context that shares its objects with the context of a window.
+    /// `opengl.sharing` must be the context of the window.
+    pub fn new_shared_with_window(_: &::winit::Window, _: (u32, u32),
+                                  pf_reqs: &PixelFormatRequirements,
+                                  opengl: &GlAttributes<&Context>)
+                                  -> Result<HeadlessContext, CreationError>
+    {
+        let share = opengl.sharing.map(|ctxt| *ctxt.gl).unwrap_or(nil);
+        HeadlessContext::with_share_context(pf_reqs, opengl, share)
+    }
+
+    fn with_share_context<T>(pf_reqs: &PixelFormatRequirements, opengl: &GlAttributes<&T>,
+                             share: id)
+                             -> Result<HeadlessContext, CreationError>
     {
         let gl_profile = helpers::get_gl_profile(opengl)?;
         let attributes = helpers::build_nsattributes(pf_reqs, gl_profile)?;
-        let context = unsafe {
+        let (context, pixel_format) = unsafe {
             let pixelformat = NSOpenGLPixelFormat::alloc(nil).initWithAttributes_(&attributes);
             if pixelformat == nil {
                 return Err(OsError(format!("Could not create the pixel format")));
             }
-            let context = NSOpenGLContext::alloc(nil).initWithFormat_shareContext_(pixelformat, nil);
+            let context = NSOpenGLContext::alloc(nil)
+                .initWithFormat_shareContext_(pixelformat, share);
             if context == nil {
                 return Err(OsError(format!("Could not create the rendering context")));
             }
-            context
+            (context, helpers::get_pixel_format(pixelformat, context))
         };
 
         let headless = HeadlessContext {
             context,
+            pixel_format,
+            info: helpers::get_context_info(gl_profile),
+            extensions: Extensions::default(),
         };
 
         Ok(headless)
@@ -83,13 +122,34 @@ impl HeadlessContext {
 
     #[inline]
     pub fn get_pixel_format(&self) -> PixelFormat {
-        unimplemented!();
+        self.pixel_format.clone()
+    }
+
+    #[inline]
+    pub fn get_context_info(&self) -> ContextInfo {
+        self.info.clone()
+    }
+
+    #[inline]
+    pub fn get_platform_extensions(&self) -> &Extensions {
+        &self.extensions
     }
 
     #[inline]
     pub unsafe fn raw_handle(&self) -> *mut c_void {
         self.context as *mut _
     }
+
+    #[inline]
+    pub fn resize(&self, _width: u32, _height: u32) -> Result<(), CreationError> {
+        // the context doesn't have any drawable
+        Ok(())
+    }
+
+    #[inline]
+    pub unsafe fn resolve_framebuffer(&self) -> u32 {
+        0
+    }
 }
 
 unsafe impl Send for HeadlessContext {}
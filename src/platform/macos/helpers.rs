@@ -1,12 +1,57 @@
 
+use ContextInfo;
 use CreationError;
 use GlAttributes;
 use GlProfile;
 use GlRequest;
+use PixelFormat;
 use PixelFormatRequirements;
 use ReleaseBehavior;
+use Robustness;
 use cocoa::appkit::*;
-use cocoa::base::nil;
+use cocoa::base::{id, nil};
+
+/// Returns the attributes of `pixel_format` on the virtual screen of `context`.
+pub unsafe fn get_pixel_format(pixel_format: id, context: id) -> PixelFormat {
+    let get_attr = |attrib: NSOpenGLPixelFormatAttribute| -> i32 {
+        let mut value = 0;
+        NSOpenGLPixelFormat::getValues_forAttribute_forVirtualScreen_(
+            pixel_format,
+            &mut value,
+            attrib,
+            NSOpenGLContext::currentVirtualScreen(context));
+        value
+    };
+
+    // NSOpenGL only reports the total size of the color buffer
+    let color_bits = (get_attr(NSOpenGLPFAColorSize) - get_attr(NSOpenGLPFAAlphaSize)) as u8;
+
+    PixelFormat {
+        hardware_accelerated: get_attr(NSOpenGLPFAAccelerated) != 0,
+        color_bits: color_bits,
+        red_bits: color_bits / 3,
+        green_bits: color_bits / 3,
+        blue_bits: color_bits / 3,
+        alpha_bits: get_attr(NSOpenGLPFAAlphaSize) as u8,
+        depth_bits: get_attr(NSOpenGLPFADepthSize) as u8,
+        stencil_bits: get_attr(NSOpenGLPFAStencilSize) as u8,
+        accum_bits: (get_attr(NSOpenGLPFAAccumSize) / 4) as u8,
+        aux_buffers: get_attr(NSOpenGLPFAAuxBuffers) as u8,
+        stereoscopy: get_attr(NSOpenGLPFAStereo) != 0,
+        double_buffer: get_attr(NSOpenGLPFADoubleBuffer) != 0,
+        multisampling: if get_attr(NSOpenGLPFAMultisample) > 0 {
+            Some(get_attr(NSOpenGLPFASamples) as u16)
+        } else {
+            None
+        },
+        srgb: true,
+        float_color_buffer: get_attr(NSOpenGLPFAColorFloat) != 0,
+        transparent: false,
+        release_behavior: ReleaseBehavior::Flush,
+        native_visual_id: None,
+        config_id: None,
+    }
+}
 
 pub fn get_gl_profile<T>(
     opengl: &GlAttributes<&T>
@@ -60,16 +105,39 @@ pub fn get_gl_profile<T>(
     }
 }
 
+/// Returns the version and profile of the contexts created with `profile`. The core profiles of
+/// macOS are always forward-compatible.
+pub fn get_context_info(profile: NSOpenGLPFAOpenGLProfiles) -> ContextInfo {
+    let (version, profile) = match profile {
+        NSOpenGLProfileVersion4_1Core => ((4, 1), Some(GlProfile::Core)),
+        NSOpenGLProfileVersion3_2Core => ((3, 2), Some(GlProfile::Core)),
+        NSOpenGLProfileVersionLegacy => ((2, 1), None),
+    };
+
+    ContextInfo {
+        version: version,
+        profile: profile,
+        debug: false,
+        forward_compatible: profile.is_some(),
+        robustness: Robustness::NotRobust,
+    }
+}
+
 pub fn build_nsattributes(
     pf_reqs: &PixelFormatRequirements, profile: NSOpenGLPFAOpenGLProfiles
 ) -> Result<Vec<u32>, CreationError> {
+    // the configurations of `available_configs` are GLX and EGL ones
+    if pf_reqs.config_id.is_some() {
+        return Err(CreationError::NoAvailablePixelFormat);
+    }
+
     // NOTE: OS X no longer has the concept of setting individual
     // color component's bit size. Instead we can only specify the
     // full color size and hope for the best. Another hiccup is that
     // `NSOpenGLPFAColorSize` also includes `NSOpenGLPFAAlphaSize`,
     // so we have to account for that as well.
     let alpha_depth = pf_reqs.alpha_bits.unwrap_or(8);
-    let color_depth = pf_reqs.color_bits.unwrap_or(24) + alpha_depth;
+    let color_depth = pf_reqs.rgb_bits().map_or(24, |(r, g, b)| r + g + b) + alpha_depth;
 
     let mut attributes = vec![
         NSOpenGLPFAOpenGLProfile as u32, profile as u32,
@@ -97,7 +165,8 @@ pub fn build_nsattributes(
     }
 
     if pf_reqs.stereoscopy {
-        unimplemented!();   // TODO:
+        // TODO:
+        return Err(CreationError::NoAvailablePixelFormat);
     }
 
     if pf_reqs.float_color_buffer {
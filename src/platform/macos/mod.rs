@@ -5,8 +5,11 @@ pub use self::headless::PlatformSpecificHeadlessBuilderAttributes;
 
 pub use winit::MonitorId;
 
+use ConfigDescriptor;
 use CreationError;
 use ContextError;
+use ContextInfo;
+use Extensions;
 use GlAttributes;
 use PixelFormat;
 use PixelFormatRequirements;
@@ -34,6 +37,18 @@ pub struct Context {
     // NSOpenGLContext
     gl: IdRef,
     pixel_format: PixelFormat,
+    info: ContextInfo,
+    // NSOpenGL doesn't have extensions
+    extensions: Extensions,
+    vsync: bool,
+    // whether the surface of the window is transparent
+    transparent: bool,
+}
+
+/// See the docs in the crate root file.
+#[inline]
+pub fn available_configs(_: &winit::EventsLoop) -> Result<Vec<ConfigDescriptor>, CreationError> {
+    Err(CreationError::NotSupported)
 }
 
 impl Context {
@@ -79,47 +94,47 @@ impl Context {
                 None => return Err(CreationError::NotSupported),
             };
 
-            let pixel_format = {
-                let get_attr = |attrib: appkit::NSOpenGLPixelFormatAttribute| -> i32 {
-                    let mut value = 0;
-                    NSOpenGLPixelFormat::getValues_forAttribute_forVirtualScreen_(
-                        *pixel_format,
-                        &mut value,
-                        attrib,
-                        NSOpenGLContext::currentVirtualScreen(*gl_context));
-                    value
-                };
-
-                PixelFormat {
-                    hardware_accelerated: get_attr(appkit::NSOpenGLPFAAccelerated) != 0,
-                    color_bits: (get_attr(appkit::NSOpenGLPFAColorSize) - get_attr(appkit::NSOpenGLPFAAlphaSize)) as u8,
-                    alpha_bits: get_attr(appkit::NSOpenGLPFAAlphaSize) as u8,
-                    depth_bits: get_attr(appkit::NSOpenGLPFADepthSize) as u8,
-                    stencil_bits: get_attr(appkit::NSOpenGLPFAStencilSize) as u8,
-                    stereoscopy: get_attr(appkit::NSOpenGLPFAStereo) != 0,
-                    double_buffer: get_attr(appkit::NSOpenGLPFADoubleBuffer) != 0,
-                    multisampling: if get_attr(appkit::NSOpenGLPFAMultisample) > 0 {
-                        Some(get_attr(appkit::NSOpenGLPFASamples) as u16)
-                    } else {
-                        None
-                    },
-                    srgb: true,
-                }
-            };
+            let pixel_format = helpers::get_pixel_format(*pixel_format, *gl_context);
 
-            gl_context.setView_(view);
-            let value = if gl_attr.vsync { 1 } else { 0 };
-            gl_context.setValues_forParameter_(&value, appkit::NSOpenGLContextParameter::NSOpenGLCPSwapInterval);
+            setup_context(*gl_context, view, gl_attr.vsync, transparent);
 
-            if transparent {
-                let mut opacity = 0;
-                CGLSetParameter(gl_context.CGLContextObj() as *mut _, kCGLCPSurfaceOpacity, &mut opacity);
-            }
+            let context = Context {
+                gl: gl_context,
+                pixel_format: pixel_format,
+                info: helpers::get_context_info(gl_profile),
+                extensions: Extensions::default(),
+                vsync: gl_attr.vsync,
+                transparent: transparent,
+            };
+            Ok((window, context))
+        }
+    }
 
-            CGLEnable(gl_context.CGLContextObj() as *mut _, kCGLCECrashOnRemovedFunctions);
+    /// Creates a new context for `window`, the window of this context, with the same pixel
+    /// format. `gl_attr.sharing` must be `None`.
+    pub fn recreate(&self, window: &winit::Window, gl_attr: &GlAttributes<&Context>)
+                    -> Result<Self, CreationError>
+    {
+        unsafe {
+            let pixel_format: id = msg_send![*self.gl, pixelFormat];
+            let gl_context = IdRef::new(NSOpenGLContext::alloc(nil)
+                .initWithFormat_shareContext_(pixel_format, nil));
+            let gl_context = match gl_context.non_nil() {
+                Some(gl_context) => gl_context,
+                None => return Err(CreationError::NotSupported),
+            };
 
-            let context = Context { gl: gl_context, pixel_format: pixel_format };
-            Ok((window, context))
+            setup_context(*gl_context, window.get_nsview() as id, gl_attr.vsync,
+                          self.transparent);
+
+            Ok(Context {
+                gl: gl_context,
+                pixel_format: self.pixel_format.clone(),
+                info: self.info.clone(),
+                extensions: Extensions::default(),
+                vsync: gl_attr.vsync,
+                transparent: self.transparent,
+            })
         }
     }
 
@@ -181,12 +196,41 @@ impl Context {
         self.pixel_format.clone()
     }
 
+    #[inline]
+    pub fn get_context_info(&self) -> ContextInfo {
+        self.info.clone()
+    }
+
+    #[inline]
+    pub fn get_platform_extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    #[inline]
+    pub fn get_vsync(&self) -> Option<bool> {
+        Some(self.vsync)
+    }
+
     #[inline]
     pub unsafe fn raw_handle(&self) -> *mut c_void {
         *self.gl.deref() as *mut _
     }
 }
 
+/// Attaches `gl_context` to `view` and sets its parameters.
+unsafe fn setup_context(gl_context: id, view: id, vsync: bool, transparent: bool) {
+    gl_context.setView_(view);
+    let value = if vsync { 1 } else { 0 };
+    gl_context.setValues_forParameter_(&value, appkit::NSOpenGLContextParameter::NSOpenGLCPSwapInterval);
+
+    if transparent {
+        let mut opacity = 0;
+        CGLSetParameter(gl_context.CGLContextObj() as *mut _, kCGLCPSurfaceOpacity, &mut opacity);
+    }
+
+    CGLEnable(gl_context.CGLContextObj() as *mut _, kCGLCECrashOnRemovedFunctions);
+}
+
 struct IdRef(id);
 
 impl IdRef {
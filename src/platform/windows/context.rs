@@ -6,6 +6,8 @@ use winapi::shared::windef::HWND;
 use winit;
 
 use ContextError;
+use ContextInfo;
+use Extensions;
 use CreationError;
 use GlAttributes;
 use GlRequest;
@@ -54,7 +56,8 @@ impl Context {
                                EglContext::new(egl.clone(),
                                                &pf_reqs,
                                                &gl_attr.clone().map_sharing(|_| unimplemented!()),
-                                               egl::NativeDisplay::Other(Some(ptr::null())))
+                                               egl::NativeDisplay::Other(Some(ptr::null())),
+                                               egl::SurfaceType::Window)
                             .and_then(|p| p.finish(w)) {
                             Ok(Context::Egl(c))
                         } else {
@@ -72,6 +75,24 @@ impl Context {
         context_result.map(|context| (window, context))
     }
 
+    /// Creates a new context for `window`, the window of this context, with the same pixel
+    /// format. `gl_attr.sharing` must be `None`.
+    pub fn recreate(&self, window: &winit::Window, gl_attr: &GlAttributes<&Self>)
+                    -> Result<Self, CreationError>
+    {
+        match *self {
+            Context::Wgl(ref c) => {
+                let gl_attr = gl_attr.clone().map_sharing(|_| unreachable!());
+                unsafe { c.recreate(&gl_attr, window.platform_window() as HWND) }
+                    .map(Context::Wgl)
+            },
+            Context::Egl(ref c) => {
+                let gl_attr = gl_attr.clone().map_sharing(|_| unreachable!());
+                c.recreate(&gl_attr).map(Context::Egl)
+            },
+        }
+    }
+
     #[inline]
     pub fn resize(&self, _width: u32, _height: u32) {
         // Method is for API consistency.
@@ -125,6 +146,31 @@ impl Context {
         }
     }
 
+    #[inline]
+    pub fn get_context_info(&self) -> ContextInfo {
+        match *self {
+            Context::Wgl(ref c) => c.get_context_info(),
+            Context::Egl(ref c) => c.get_context_info(),
+        }
+    }
+
+    #[inline]
+    pub fn get_platform_extensions(&self) -> &Extensions {
+        match *self {
+            Context::Wgl(ref c) => c.get_extensions(),
+            Context::Egl(ref c) => c.get_extensions(),
+        }
+    }
+
+    /// Returns whether vsync is enabled, or `None` if it is unknown.
+    #[inline]
+    pub fn get_vsync(&self) -> Option<bool> {
+        match *self {
+            Context::Wgl(ref c) => c.get_vsync(),
+            Context::Egl(_) => None,
+        }
+    }
+
     #[inline]
     pub unsafe fn raw_handle(&self) -> RawHandle {
         match *self {
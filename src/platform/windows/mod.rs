@@ -8,7 +8,10 @@ use winapi::um::libloaderapi::*;
 use winit;
 
 use Api;
+use ConfigDescriptor;
 use ContextError;
+use ContextInfo;
+use Extensions;
 use CreationError;
 use PixelFormat;
 use PixelFormatRequirements;
@@ -62,6 +65,12 @@ lazy_static! {
 /// The Win32 implementation of the main `Context` object.
 pub struct Context(context::Context);
 
+/// See the docs in the crate root file.
+#[inline]
+pub fn available_configs(_: &winit::EventsLoop) -> Result<Vec<ConfigDescriptor>, CreationError> {
+    Err(CreationError::NotSupported)
+}
+
 impl Context {
     /// See the docs in the crate root file.
     #[inline]
@@ -79,6 +88,14 @@ impl Context {
             EGL.as_ref().map(|w| &w.0),
         ).map(|(w, c)| (w, Context(c)))
     }
+
+    /// See the docs in the crate root file.
+    #[inline]
+    pub fn recreate(&self, window: &winit::Window, opengl: &GlAttributes<&Self>)
+                    -> Result<Self, CreationError>
+    {
+        self.0.recreate(window, &opengl.clone().map_sharing(|w| &w.0)).map(Context)
+    }
 }
 
 impl Deref for Context {
@@ -108,6 +125,14 @@ pub enum HeadlessContext {
 }
 
 impl HeadlessContext {
+    /// See the docs in the crate root file.
+    #[inline]
+    pub fn available_configs(_: &PlatformSpecificHeadlessBuilderAttributes)
+                             -> Result<Vec<ConfigDescriptor>, CreationError>
+    {
+        Err(CreationError::NotSupported)
+    }
+
     pub fn new(
         dimensions: (u32, u32),
         pf_reqs: &PixelFormatRequirements,
@@ -115,12 +140,18 @@ impl HeadlessContext {
         _: &PlatformSpecificHeadlessBuilderAttributes,
     ) -> Result<Self, CreationError>
     {
+        if gl_attr.sharing.is_some() {
+            let msg = "Context sharing is not supported with headless contexts on Windows";
+            return Err(CreationError::PlatformSpecific(msg.into()));
+        }
+
         // if EGL is available, we try using EGL first
         // if EGL returns an error, we try the hidden window method
         if let &Some(ref egl) = &*EGL {
             let gl_attr = &gl_attr.clone().map_sharing(|_| unimplemented!()); // TODO
             let native_display = egl::NativeDisplay::Other(None);
-            let context = EglContext::new(egl.0.clone(), pf_reqs, &gl_attr, native_display)
+            let context = EglContext::new(egl.0.clone(), pf_reqs, &gl_attr, native_display,
+                                          egl::SurfaceType::PBuffer)
                 .and_then(|prototype| prototype.finish_pbuffer(dimensions))
                 .map(|ctxt| HeadlessContext::EglPbuffer(ctxt));
             if let Ok(context) = context {
@@ -135,6 +166,43 @@ impl HeadlessContext {
             .map(|(window, context)| HeadlessContext::HiddenWindow(events_loop, window, context))
     }
 
+    /// Creates a context that shares its objects with the context of a window. `gl_attr.sharing`
+    /// must be the context of the window.
+    ///
+    /// An EGL pbuffer is used if the window uses EGL, and a hidden window otherwise.
+    pub fn new_shared_with_window(
+        _: &winit::Window,
+        dimensions: (u32, u32),
+        pf_reqs: &PixelFormatRequirements,
+        gl_attr: &GlAttributes<&Context>,
+    ) -> Result<Self, CreationError>
+    {
+        let window_context = gl_attr.sharing.unwrap();
+        match **window_context {
+            context::Context::Egl(ref ctxt) => {
+                let egl = EGL.as_ref().map(|w| &w.0).unwrap();
+                let gl_attr = gl_attr.clone().map_sharing(|_| ctxt);
+                let native_display = egl::NativeDisplay::Other(None);
+                EglContext::new(egl.clone(), pf_reqs, &gl_attr, native_display,
+                                egl::SurfaceType::PBuffer)
+                    .and_then(|prototype| prototype.finish_pbuffer(dimensions))
+                    .map(|ctxt| HeadlessContext::EglPbuffer(ctxt))
+            },
+            context::Context::Wgl(_) => {
+                let events_loop = winit::EventsLoop::new();
+                let window_builder = winit::WindowBuilder::new().with_visibility(false)
+                                                                .with_dimensions(dimensions.0,
+                                                                                 dimensions.1);
+                let gl_attr = gl_attr.clone().map_sharing(|ctxt| &ctxt.0);
+                let egl = EGL.as_ref().map(|w| &w.0);
+                context::Context::new(window_builder, &events_loop, pf_reqs, &gl_attr, egl)
+                    .map(|(window, context)| {
+                        HeadlessContext::HiddenWindow(events_loop, window, context)
+                    })
+            },
+        }
+    }
+
     #[inline]
     pub unsafe fn make_current(&self) -> Result<(), ContextError> {
         match self {
@@ -183,6 +251,22 @@ impl HeadlessContext {
         }
     }
 
+    #[inline]
+    pub fn get_context_info(&self) -> ContextInfo {
+        match self {
+            &HeadlessContext::HiddenWindow(_, _, ref ctxt) => ctxt.get_context_info(),
+            &HeadlessContext::EglPbuffer(ref ctxt) => ctxt.get_context_info(),
+        }
+    }
+
+    #[inline]
+    pub fn get_platform_extensions(&self) -> &Extensions {
+        match self {
+            &HeadlessContext::HiddenWindow(_, _, ref ctxt) => ctxt.get_platform_extensions(),
+            &HeadlessContext::EglPbuffer(ref ctxt) => ctxt.get_extensions(),
+        }
+    }
+
     #[inline]
     pub unsafe fn raw_handle(&self) -> RawHandle {
         match *self {
@@ -190,4 +274,22 @@ impl HeadlessContext {
             HeadlessContext::EglPbuffer(ref ctxt) => RawHandle::Egl(ctxt.raw_handle()),
         }
     }
+
+    #[inline]
+    pub fn resize(&self, width: u32, height: u32) -> Result<(), CreationError> {
+        match *self {
+            HeadlessContext::HiddenWindow(_, ref window, _) => {
+                window.set_inner_size(width, height);
+                Ok(())
+            },
+            HeadlessContext::EglPbuffer(ref ctxt) => unsafe {
+                ctxt.resize_pbuffer((width, height))
+            },
+        }
+    }
+
+    #[inline]
+    pub unsafe fn resolve_framebuffer(&self) -> u32 {
+        0
+    }
 }
@@ -0,0 +1,76 @@
+use Api;
+use ContextError;
+use GlContext;
+use Robustness;
+
+use api::gl;
+use api::gl::types::GLenum;
+
+use std::mem;
+
+/// Whether a context was lost because of a graphics reset, see `GlContext::get_reset_status`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ResetStatus {
+    /// The context wasn't reset.
+    NoError,
+
+    /// The context was reset because of something it did, for example a shader that didn't
+    /// terminate.
+    GuiltyContextReset,
+
+    /// The context was reset because of another context.
+    InnocentContextReset,
+
+    /// The context was reset for an unknown reason.
+    UnknownContextReset,
+}
+
+/// Queries the reset status of `context`, which must be current.
+pub(crate) fn get_reset_status<C: ?Sized + GlContext>(context: &C)
+                                                      -> Result<ResetStatus, ContextError>
+{
+    if !context.is_current() {
+        return Err(ContextError::NotCurrent);
+    }
+
+    // GLX and EGL return an address for every function, supported or not, so the function is
+    // only called on contexts that can be notified of resets
+    let info = context.get_context_info();
+    if info.robustness != Robustness::RobustLoseContextOnReset {
+        return Ok(ResetStatus::NoError);
+    }
+
+    let core = match context.get_api() {
+        Api::OpenGl => info.version >= (4, 5),
+        _ => info.version >= (3, 2),
+    };
+    let name = if core {
+        "glGetGraphicsResetStatus"
+    } else {
+        // only needed by older versions, as the status is usually queried every frame
+        let extensions = context.get_gl_extensions()?;
+        let names = [("GL_KHR_robustness", "glGetGraphicsResetStatusKHR"),
+                     ("GL_ARB_robustness", "glGetGraphicsResetStatusARB"),
+                     ("GL_EXT_robustness", "glGetGraphicsResetStatusEXT")];
+        match names.iter().find(|&&(extension, _)| extensions.has_extension(extension)) {
+            Some(&(_, name)) => name,
+            // the context can't be robust without one of these extensions
+            None => return Ok(ResetStatus::NoError),
+        }
+    };
+
+    let address = context.get_proc_address(name);
+    if address.is_null() {
+        return Ok(ResetStatus::NoError);
+    }
+    let get_graphics_reset_status: extern "system" fn() -> GLenum = unsafe {
+        mem::transmute(address)
+    };
+
+    Ok(match get_graphics_reset_status() {
+        gl::GUILTY_CONTEXT_RESET => ResetStatus::GuiltyContextReset,
+        gl::INNOCENT_CONTEXT_RESET => ResetStatus::InnocentContextReset,
+        gl::UNKNOWN_CONTEXT_RESET => ResetStatus::UnknownContextReset,
+        _ => ResetStatus::NoError,
+    })
+}
@@ -0,0 +1,226 @@
+use CreationError;
+
+use platform;
+
+use std::mem;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, ThreadId};
+
+/// Group of contexts that share their objects (textures, buffers, ...).
+///
+/// Contrary to `with_shared_lists`, a share group doesn't borrow any context. It can be cloned
+/// and sent to other threads, and contexts can be built into it at any time with
+/// `GlBuilder::with_share_group`.
+///
+/// The first context built into the group is the one that every other context of the group
+/// shares with. Its native context is kept alive until the group and all the contexts of the
+/// group are dropped, even if the `GlWindow` or `HeadlessContext` it was built with is dropped
+/// before. The window itself is destroyed as usual. The other contexts are destroyed along with
+/// their `GlWindow` or `HeadlessContext`, before the first one.
+///
+/// Some native contexts must be destroyed on the thread that created them: window contexts on
+/// macOS, iOS, Android and emscripten, and headless contexts on Windows. If the last handle to
+/// the group is dropped on another thread, the first context is sent back to its thread, which
+/// destroys it the next time it builds a context into a share group, or when it exits.
+///
+/// A share group contains either window contexts or headless contexts, but not both.
+///
+/// # Example
+///
+/// ```no_run
+/// # extern crate glutin;
+/// use glutin::GlBuilder;
+/// # fn main() {
+/// let group = glutin::ShareGroup::new();
+/// let main_context = glutin::HeadlessRendererBuilder::new(256, 256)
+///     .with_share_group(&group)
+///     .build()
+///     .unwrap();
+///
+/// let worker_group = group.clone();
+/// std::thread::spawn(move || {
+///     let worker_context = glutin::HeadlessRendererBuilder::new(1, 1)
+///         .with_share_group(&worker_group)
+///         .build()
+///         .unwrap();
+///     // ... upload textures ...
+/// });
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct ShareGroup {
+    // locked while a context of the group is being created
+    root: Arc<Mutex<Option<Root>>>,
+}
+
+/// The first context of a group.
+struct Root {
+    // `None` once the root is dropped
+    context: Option<Native>,
+    // the thread that must destroy the context, if any
+    owner: Option<Arc<Graveyard>>,
+}
+
+/// A native context of a group.
+pub(crate) enum Native {
+    Window(Arc<platform::Context>),
+    Headless(Arc<platform::HeadlessContext>),
+}
+
+// The native context is only used behind the mutex of the group, as the context to share with
+// when creating other contexts. A native context that must be destroyed on the thread that
+// created it is sent back to that thread, see `Graveyard`.
+unsafe impl Send for Native {}
+
+/// Native contexts that can be stored in a `ShareGroup`.
+pub(crate) trait Member: Sized {
+    /// Returns true if the native context must be destroyed on the thread that created it.
+    fn thread_bound() -> bool;
+    fn from_native(native: &Native) -> Option<&Arc<Self>>;
+    fn into_native(context: Arc<Self>) -> Native;
+}
+
+impl Member for platform::Context {
+    #[inline]
+    fn thread_bound() -> bool {
+        // only the window contexts of X11, Wayland and Windows can be destroyed on any thread
+        !cfg!(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd",
+                  target_os = "openbsd", target_os = "windows"))
+    }
+
+    #[inline]
+    fn from_native(native: &Native) -> Option<&Arc<Self>> {
+        match *native {
+            Native::Window(ref context) => Some(context),
+            Native::Headless(_) => None,
+        }
+    }
+
+    #[inline]
+    fn into_native(context: Arc<Self>) -> Native {
+        Native::Window(context)
+    }
+}
+
+impl Member for platform::HeadlessContext {
+    #[inline]
+    fn thread_bound() -> bool {
+        // the hidden window of a headless context must be destroyed by its thread on Windows
+        cfg!(target_os = "windows")
+    }
+
+    #[inline]
+    fn from_native(native: &Native) -> Option<&Arc<Self>> {
+        match *native {
+            Native::Headless(ref context) => Some(context),
+            Native::Window(_) => None,
+        }
+    }
+
+    #[inline]
+    fn into_native(context: Arc<Self>) -> Native {
+        Native::Headless(context)
+    }
+}
+
+impl ShareGroup {
+    /// Creates an empty share group.
+    #[inline]
+    pub fn new() -> ShareGroup {
+        Default::default()
+    }
+
+    /// Creates a context of the group with `build`, which receives the native context to share
+    /// with, or `None` if the new context is the first one of the group.
+    pub(crate) fn build<T, R, F>(&self, build: F) -> Result<(Arc<T>, R), CreationError>
+        where T: Member, F: FnOnce(Option<&T>) -> Result<(Arc<T>, R), CreationError>
+    {
+        Graveyard::collect();
+        let mut root = self.root.lock().unwrap();
+
+        let (context, result) = match *root {
+            // the native context isn't cloned, so that it can't be dropped on this thread
+            Some(ref root) => match root.context.as_ref().and_then(T::from_native) {
+                Some(shared) => return build(Some(&**shared)),
+                None => {
+                    let msg = "A share group can't contain both window and headless contexts";
+                    return Err(CreationError::PlatformSpecific(msg.into()));
+                },
+            },
+            None => build(None)?,
+        };
+
+        *root = Some(Root {
+            context: Some(T::into_native(context.clone())),
+            owner: if T::thread_bound() { Some(Graveyard::current()) } else { None },
+        });
+        Ok((context, result))
+    }
+}
+
+impl Drop for Root {
+    fn drop(&mut self) {
+        if let Some(ref owner) = self.owner {
+            if let Some(context) = self.context.take() {
+                owner.bury(context);
+            }
+        }
+    }
+}
+
+/// The native contexts created by a thread that were released on other threads. The thread
+/// destroys them the next time it builds a context into a share group, or when it exits.
+struct Graveyard {
+    thread: ThreadId,
+    // `None` once the thread has exited
+    contexts: Mutex<Option<Vec<Native>>>,
+}
+
+/// Destroys the contexts of the graveyard of its thread when the thread exits.
+struct ThreadGraveyard(Arc<Graveyard>);
+
+thread_local!(static GRAVEYARD: ThreadGraveyard = ThreadGraveyard(Arc::new(Graveyard {
+    thread: thread::current().id(),
+    contexts: Mutex::new(Some(Vec::new())),
+})));
+
+impl Graveyard {
+    /// Returns the graveyard of the current thread.
+    #[inline]
+    fn current() -> Arc<Graveyard> {
+        GRAVEYARD.with(|graveyard| graveyard.0.clone())
+    }
+
+    /// Destroys the contexts that were sent back to the current thread.
+    fn collect() {
+        GRAVEYARD.with(|graveyard| {
+            let mut contexts = graveyard.0.contexts.lock().unwrap();
+            let released = mem::replace(&mut *contexts, Some(Vec::new()));
+            drop(contexts);
+            drop(released);
+        });
+    }
+
+    /// Destroys `context` if called by the thread of the graveyard, and sends it to that thread
+    /// otherwise.
+    fn bury(&self, context: Native) {
+        if thread::current().id() != self.thread {
+            let mut contexts = self.contexts.lock().unwrap();
+            if let Some(ref mut contexts) = *contexts {
+                contexts.push(context);
+                return;
+            }
+        }
+
+        // either on the right thread, or the thread has exited along with its windows and
+        // nothing else can destroy the context
+        drop(context);
+    }
+}
+
+impl Drop for ThreadGraveyard {
+    fn drop(&mut self) {
+        let released = self.0.contexts.lock().unwrap().take();
+        drop(released);
+    }
+}
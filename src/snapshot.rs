@@ -0,0 +1,326 @@
+//! Saving what was rendered to an image file.
+//!
+//! This module is only available with the `snapshot` Cargo feature. Images can be encoded as
+//! PNG, binary PPM (`P6`, without alpha) or PAM (`P7`, with alpha) without any additional
+//! dependency.
+//!
+//! ```no_run
+//! # extern crate glutin;
+//! # fn main() {
+//! use glutin::GlContext;
+//! use glutin::ImageFormat;
+//!
+//! # let context: glutin::HeadlessContext = unimplemented!();
+//! unsafe { context.make_current().unwrap() };
+//! // ... draw ...
+//! let image = glutin::snapshot::capture(&context, (256, 256), ImageFormat::Rgba8).unwrap();
+//! image.save("frame.png").unwrap();
+//! # }
+//! ```
+
+use ContextError;
+use GlContext;
+use Image;
+use ImageFormat;
+use RowOrder;
+
+use api::gl;
+use api::gl::types::GLuint;
+use framebuffer;
+
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::mem;
+use std::path::Path;
+
+/// Reads the pixels of the framebuffer that is currently bound for reading, which is the
+/// default framebuffer unless another one was bound.
+///
+/// The image is returned with its rows top-down. The context must be current.
+///
+/// For a `HeadlessContext`, prefer `HeadlessContext::read_pixels`, which also resolves
+/// multisampled framebuffers.
+///
+/// Returns `ContextError::NotCurrent` if the context is not current.
+pub fn capture<C: ?Sized>(context: &C, dimensions: (u32, u32), format: ImageFormat)
+                          -> Result<Image, ContextError>
+    where C: GlContext
+{
+    if !context.is_current() {
+        return Err(ContextError::NotCurrent);
+    }
+
+    let gl = gl::Gl::load_with(|symbol| context.get_proc_address(symbol) as *const _);
+    unsafe {
+        let (_, version) = gl::get_version(&gl);
+        let binding = if version >= (3, 0) {
+            gl::READ_FRAMEBUFFER_BINDING
+        } else {
+            gl::FRAMEBUFFER_BINDING
+        };
+        let framebuffer = gl::get_integer(&gl, binding) as GLuint;
+        Ok(framebuffer::read_pixels(&gl, framebuffer, dimensions, format, RowOrder::TopDown))
+    }
+}
+
+/// Captures the framebuffer that is currently bound for reading and saves it to `path`.
+///
+/// See `capture` and `Image::save`. The alpha channel is kept if the format supports it.
+pub fn save<C: ?Sized, P>(context: &C, dimensions: (u32, u32), path: P) -> io::Result<()>
+    where C: GlContext, P: AsRef<Path>
+{
+    capture(context, dimensions, ImageFormat::Rgba8)
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?
+        .save(path)
+}
+
+impl Image {
+    /// Saves the image to a file. The format is chosen from the extension of the path, which
+    /// must be `png`, `ppm` or `pam`.
+    ///
+    /// This method is only available with the `snapshot` Cargo feature.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let extension = path.extension()
+                            .and_then(|ext| ext.to_str())
+                            .map(|ext| ext.to_lowercase());
+
+        let write: fn(&Image, &mut BufWriter<File>) -> io::Result<()> = match extension {
+            Some(ref ext) if ext == "png" => |image, out| image.write_png(out),
+            Some(ref ext) if ext == "ppm" => |image, out| image.write_ppm(out),
+            Some(ref ext) if ext == "pam" => |image, out| image.write_pam(out),
+            _ => {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                          format!("unsupported image extension for {}",
+                                                  path.display())));
+            },
+        };
+
+        let mut out = BufWriter::new(File::create(path)?);
+        write(self, &mut out)?;
+        out.flush()
+    }
+
+    /// Encodes the image as PNG.
+    ///
+    /// 8-bit images are written with 8 bits per channel, `RgbaF32` images are clamped to
+    /// `[0, 1]` and written with 16 bits per channel.
+    ///
+    /// This method is only available with the `snapshot` Cargo feature.
+    pub fn write_png<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let samples = self.samples();
+        let channels = self.channels();
+        let row_len = self.width as usize * channels * samples.bytes_per_sample();
+
+        // every row is preceded by its filter type, which is always `None`
+        let mut raw = Vec::with_capacity((row_len + 1) * self.height as usize);
+        for row in samples.rows_top_down(self) {
+            raw.push(0);
+            raw.extend_from_slice(row);
+        }
+
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&be_u32(self.width));
+        header.extend_from_slice(&be_u32(self.height));
+        header.push(samples.bit_depth());
+        header.push(if channels == 4 { 6 } else { 2 });     // RGBA or RGB
+        header.extend_from_slice(&[0, 0, 0]);       // deflate, adaptive filtering, no interlace
+
+        out.write_all(b"\x89PNG\r\n\x1a\n")?;
+        write_png_chunk(out, b"IHDR", &header)?;
+        write_png_chunk(out, b"IDAT", &zlib_stored(&raw))?;
+        write_png_chunk(out, b"IEND", &[])
+    }
+
+    /// Encodes the image as a binary PPM (`P6`). The alpha channel is dropped.
+    ///
+    /// `RgbaF32` images are clamped to `[0, 1]` and written with 16 bits per channel.
+    ///
+    /// This method is only available with the `snapshot` Cargo feature.
+    pub fn write_ppm<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let samples = self.samples();
+        let channels = self.channels();
+        let sample_len = samples.bytes_per_sample();
+
+        write!(out, "P6\n{} {}\n{}\n", self.width, self.height, samples.max_value())?;
+        for row in samples.rows_top_down(self) {
+            if channels == 3 {
+                out.write_all(row)?;
+            } else {
+                for pixel in row.chunks(4 * sample_len) {
+                    out.write_all(&pixel[.. 3 * sample_len])?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Encodes the image as a PAM (`P7`), with the `RGB_ALPHA` tuple type if the image has an
+    /// alpha channel and `RGB` otherwise.
+    ///
+    /// `RgbaF32` images are clamped to `[0, 1]` and written with 16 bits per channel.
+    ///
+    /// This method is only available with the `snapshot` Cargo feature.
+    pub fn write_pam<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let samples = self.samples();
+        let channels = self.channels();
+
+        write!(out, "P7\nWIDTH {}\nHEIGHT {}\nDEPTH {}\nMAXVAL {}\nTUPLTYPE {}\nENDHDR\n",
+               self.width, self.height, channels, samples.max_value(),
+               if channels == 4 { "RGB_ALPHA" } else { "RGB" })?;
+        for row in samples.rows_top_down(self) {
+            out.write_all(row)?;
+        }
+        Ok(())
+    }
+
+    fn channels(&self) -> usize {
+        match self.format {
+            ImageFormat::Rgb8 => 3,
+            ImageFormat::Rgba8 | ImageFormat::Bgra8 | ImageFormat::RgbaF32 => 4,
+        }
+    }
+
+    /// Converts the pixels to RGB or RGBA big-endian samples, which is what every format of this
+    /// module expects.
+    fn samples(&self) -> Samples {
+        match self.format {
+            ImageFormat::Rgba8 | ImageFormat::Rgb8 => Samples::Eight(Cow::Borrowed(&self.data)),
+            ImageFormat::Bgra8 => {
+                let mut data = self.data.clone();
+                for pixel in data.chunks_mut(4) {
+                    pixel.swap(0, 2);
+                }
+                Samples::Eight(Cow::Owned(data))
+            },
+            ImageFormat::RgbaF32 => {
+                let data = self.data.chunks(4).flat_map(|bytes| {
+                    let mut bits = [0u8; 4];
+                    bits.copy_from_slice(bytes);
+                    // the floats are in the native endianness
+                    let value = f32::from_bits(unsafe { mem::transmute::<_, u32>(bits) });
+                    let value = f32_to_u16(value);
+                    vec![(value >> 8) as u8, value as u8]
+                }).collect();
+                Samples::Sixteen(data)
+            },
+        }
+    }
+}
+
+enum Samples<'a> {
+    Eight(Cow<'a, [u8]>),
+    /// Big-endian.
+    Sixteen(Vec<u8>),
+}
+
+impl<'a> Samples<'a> {
+    fn data(&self) -> &[u8] {
+        match *self {
+            Samples::Eight(ref data) => data,
+            Samples::Sixteen(ref data) => data,
+        }
+    }
+
+    fn bytes_per_sample(&self) -> usize {
+        match *self {
+            Samples::Eight(_) => 1,
+            Samples::Sixteen(_) => 2,
+        }
+    }
+
+    fn bit_depth(&self) -> u8 {
+        self.bytes_per_sample() as u8 * 8
+    }
+
+    fn max_value(&self) -> u32 {
+        match *self {
+            Samples::Eight(_) => 255,
+            Samples::Sixteen(_) => 65535,
+        }
+    }
+
+    /// Returns the rows of the image, starting with the top one.
+    fn rows_top_down<'b>(&'b self, image: &Image) -> Box<Iterator<Item = &'b [u8]> + 'b> {
+        let data = self.data();
+        let height = image.height as usize;
+        let row_len = if height == 0 { 0 } else { data.len() / height };
+        let rows = (0 .. height).map(move |y| &data[y * row_len .. (y + 1) * row_len]);
+
+        match image.row_order {
+            RowOrder::TopDown => Box::new(rows),
+            RowOrder::BottomUp => Box::new(rows.rev()),
+        }
+    }
+}
+
+#[inline]
+fn f32_to_u16(value: f32) -> u16 {
+    // `NaN` is mapped to 0
+    if value > 0.0 {
+        (value.min(1.0) * 65535.0 + 0.5) as u16
+    } else {
+        0
+    }
+}
+
+#[inline]
+fn be_u32(value: u32) -> [u8; 4] {
+    [(value >> 24) as u8, (value >> 16) as u8, (value >> 8) as u8, value as u8]
+}
+
+fn write_png_chunk<W: Write>(out: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&be_u32(data.len() as u32))?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+    out.write_all(&be_u32(crc32(kind.iter().chain(data))))
+}
+
+/// Wraps `data` in a zlib stream made of uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    const MAX_BLOCK_LEN: usize = 0xffff;
+
+    let mut out = Vec::with_capacity(data.len() + data.len() / MAX_BLOCK_LEN * 5 + 11);
+    out.extend_from_slice(&[0x78, 0x01]);
+
+    let mut blocks = data.chunks(MAX_BLOCK_LEN).peekable();
+    if blocks.peek().is_none() {
+        // a final empty block
+        out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let len = block.len() as u16;
+        out.push(if blocks.peek().is_none() { 1 } else { 0 });
+        out.extend_from_slice(&[len as u8, (len >> 8) as u8, !len as u8, (!len >> 8) as u8]);
+        out.extend_from_slice(block);
+    }
+
+    out.extend_from_slice(&be_u32(adler32(data)));
+    out
+}
+
+fn crc32<'a, I>(data: I) -> u32 where I: IntoIterator<Item = &'a u8> {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0 .. 8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb88320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    // 5552 is the largest number of bytes that can be summed before `b` may overflow
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
@@ -0,0 +1,219 @@
+use Api;
+use CreationError;
+use GlAttributes;
+use GlContext;
+use GlProfile;
+use GlRequest;
+use PixelFormatRequirements;
+use Robustness;
+
+use api::gl;
+use api::gl::types::GLenum;
+
+/// Checks that a freshly created context fulfills the requirements it was created with.
+///
+/// The context is made current. `vsync` is whether vsync is enabled, or `None` if the backend
+/// can't tell, in which case it isn't verified.
+///
+/// Returns `CreationError::RequirementsNotMet` with the list of unmet requirements.
+pub fn verify<C, S>(context: &C, pf_reqs: &PixelFormatRequirements, opengl: &GlAttributes<S>,
+                    vsync: Option<bool>) -> Result<(), CreationError>
+    where C: GlContext
+{
+    unsafe {
+        context.make_current().map_err(|err| {
+            CreationError::OsError(format!("Couldn't make the context current to verify it: {}",
+                                           err))
+        })?;
+    }
+
+    let mut unmet = Vec::new();
+
+    // a chosen configuration replaces the pixel format requirements
+    if pf_reqs.config_id.is_none() {
+        check_pixel_format(context, pf_reqs, &mut unmet);
+    }
+
+    let gl = gl::Gl::load_with(|symbol| context.get_proc_address(symbol) as *const _);
+    unsafe {
+        let (api, version) = gl::get_version(&gl);
+        check_version(context.get_api(), api, version, opengl.version, &mut unmet);
+        if api == Api::OpenGl {
+            check_profile(&gl, version, opengl.profile, &mut unmet);
+        }
+        check_robustness(&gl, api, version, opengl.robustness, &mut unmet);
+
+        // some of the queries above don't exist in every version, don't leave their errors to
+        // the user
+        for _ in 0 .. 16 {
+            if gl.GetError() == gl::NO_ERROR {
+                break;
+            }
+        }
+    }
+
+    if opengl.vsync && vsync == Some(false) {
+        unmet.push("vsync: requested, but couldn't be enabled".to_owned());
+    }
+
+    if unmet.is_empty() {
+        Ok(())
+    } else {
+        Err(CreationError::RequirementsNotMet(unmet))
+    }
+}
+
+fn check_pixel_format<C>(context: &C, reqs: &PixelFormatRequirements, unmet: &mut Vec<String>)
+    where C: GlContext
+{
+    let format = context.get_pixel_format();
+
+    if let Some(hardware_accelerated) = reqs.hardware_accelerated {
+        if format.hardware_accelerated != hardware_accelerated {
+            unmet.push(format!("hardware_accelerated: requested {}, got {}",
+                               hardware_accelerated, format.hardware_accelerated));
+        }
+    }
+
+    {
+        let mut check_bits = |name: &str, requested: Option<u8>, got: u8| {
+            match requested {
+                Some(requested) if got < requested => {
+                    unmet.push(format!("{}: requested at least {}, got {}", name, requested, got));
+                },
+                _ => (),
+            }
+        };
+        match reqs.channel_bits {
+            Some((red, green, blue)) => {
+                check_bits("red_bits", Some(red), format.red_bits);
+                check_bits("green_bits", Some(green), format.green_bits);
+                check_bits("blue_bits", Some(blue), format.blue_bits);
+            },
+            None => check_bits("color_bits", reqs.color_bits, format.color_bits),
+        }
+        check_bits("alpha_bits", reqs.alpha_bits, format.alpha_bits);
+        check_bits("depth_bits", reqs.depth_bits, format.depth_bits);
+        check_bits("stencil_bits", reqs.stencil_bits, format.stencil_bits);
+        check_bits("accum_bits", reqs.accum_bits, format.accum_bits);
+        check_bits("aux_buffers", reqs.aux_buffers, format.aux_buffers);
+    }
+
+    if reqs.float_color_buffer && !format.float_color_buffer {
+        unmet.push("float_color_buffer: requested, but not provided".to_owned());
+    }
+
+    if let Some(double_buffer) = reqs.double_buffer {
+        if format.double_buffer != double_buffer {
+            unmet.push(format!("double_buffer: requested {}, got {}", double_buffer,
+                               format.double_buffer));
+        }
+    }
+
+    match (reqs.multisampling, format.multisampling) {
+        (Some(0), Some(got)) => {
+            unmet.push(format!("multisampling: requested none, got {} samples", got));
+        },
+        (Some(requested), got) if requested > 0 && got.unwrap_or(0) < requested => {
+            unmet.push(format!("multisampling: requested at least {} samples, got {}", requested,
+                               got.unwrap_or(0)));
+        },
+        _ => (),
+    }
+
+    if reqs.stereoscopy && !format.stereoscopy {
+        unmet.push("stereoscopy: requested, but not provided".to_owned());
+    }
+
+    if reqs.srgb && !format.srgb {
+        unmet.push("srgb: requested, but not provided".to_owned());
+    }
+
+    if reqs.release_behavior != format.release_behavior {
+        unmet.push(format!("release_behavior: requested {:?}, got {:?}", reqs.release_behavior,
+                           format.release_behavior));
+    }
+}
+
+fn check_version(context_api: Api, api: Api, version: (u8, u8), request: GlRequest,
+                 unmet: &mut Vec<String>)
+{
+    let requested = match request {
+        GlRequest::Latest => return,
+        GlRequest::Specific(requested_api, requested_version) => {
+            if requested_api != context_api {
+                unmet.push(format!("version: requested {:?}, got {:?}", requested_api,
+                                   context_api));
+                return;
+            }
+            requested_version
+        },
+        GlRequest::GlThenGles { opengl_version, opengles_version } => {
+            match api {
+                Api::OpenGl => opengl_version,
+                _ => opengles_version,
+            }
+        },
+    };
+
+    if version < requested {
+        unmet.push(format!("version: requested {:?} {}.{}, got {}.{}", api, requested.0,
+                           requested.1, version.0, version.1));
+    }
+}
+
+unsafe fn check_profile(gl: &gl::Gl, version: (u8, u8), requested: Option<GlProfile>,
+                        unmet: &mut Vec<String>)
+{
+    let requested = match requested {
+        Some(profile) => profile,
+        None => return,
+    };
+
+    // profiles only exist since OpenGL 3.2, older contexts behave like compatibility ones
+    let got = if version >= (3, 2) {
+        let mask = gl::get_integer(gl, gl::CONTEXT_PROFILE_MASK) as GLenum;
+        if mask & gl::CONTEXT_CORE_PROFILE_BIT != 0 {
+            GlProfile::Core
+        } else {
+            GlProfile::Compatibility
+        }
+    } else {
+        GlProfile::Compatibility
+    };
+
+    if got != requested {
+        unmet.push(format!("profile: requested {:?}, got {:?}", requested, got));
+    }
+}
+
+unsafe fn check_robustness(gl: &gl::Gl, api: Api, version: (u8, u8), requested: Robustness,
+                           unmet: &mut Vec<String>)
+{
+    let expected_strategy = match requested {
+        Robustness::RobustNoResetNotification => gl::NO_RESET_NOTIFICATION,
+        Robustness::RobustLoseContextOnReset => gl::LOSE_CONTEXT_ON_RESET,
+        // the other values are allowed to silently fall back to `NotRobust`
+        _ => return,
+    };
+
+    // `GL_CONTEXT_FLAGS` only exists since OpenGL 3.0 and OpenGL ES 3.2
+    let has_context_flags = match api {
+        Api::OpenGl => version >= (3, 0),
+        _ => version >= (3, 2),
+    };
+    if has_context_flags {
+        let flags = gl::get_integer(gl, gl::CONTEXT_FLAGS) as GLenum;
+        if flags & gl::CONTEXT_FLAG_ROBUST_ACCESS_BIT == 0 {
+            unmet.push(format!("robustness: requested {:?}, but robust access is disabled",
+                               requested));
+            return;
+        }
+    }
+
+    let strategy = gl::get_integer(gl, gl::RESET_NOTIFICATION_STRATEGY) as GLenum;
+    if strategy != expected_strategy {
+        unmet.push(format!("robustness: requested {:?}, but the reset notification strategy \
+                            is 0x{:x}", requested, strategy));
+    }
+}
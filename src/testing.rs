@@ -0,0 +1,350 @@
+//! Golden-image tests.
+//!
+//! This module is only available with the `testing` Cargo feature, which enables the `snapshot`
+//! feature as well. It renders into a headless context and compares the result to a reference
+//! image stored as a PAM file, with a per-channel tolerance.
+//!
+//! On Linux, the context is created with the first headless backend that works, so the tests
+//! also run without any GPU with Mesa's software renderers.
+//!
+//! If the reference image doesn't exist, the test fails unless the `GLUTIN_UPDATE_REFERENCES`
+//! environment variable is set, in which case the rendered image becomes the reference. When
+//! the images don't match, the rendered image is written next to the reference with the
+//! `.actual.pam` extension, and an image showing the pixels that differ in red with the
+//! `.diff.png` extension.
+//!
+//! Machines without any headless backend skip the tests that use `skip_without_backend`, with
+//! a message on stderr. Set the `GLUTIN_REQUIRE_BACKEND` environment variable where a backend is
+//! expected, for example on CI, to make them fail instead.
+//!
+//! ```no_run
+//! # extern crate glutin;
+//! # fn main() {
+//! let image = glutin::testing::render(64, 64, |context| {
+//!     // ... draw with `context` ...
+//! });
+//! let image = match glutin::testing::skip_without_backend("triangle", image) {
+//!     Some(image) => image,
+//!     None => return,
+//! };
+//!
+//! glutin::testing::assert_matches(&image, "tests/references/triangle.pam", 2);
+//! # }
+//! ```
+
+use CreationError;
+use GlContext;
+use HeadlessContext;
+use HeadlessRendererBuilder;
+use Image;
+use ImageFormat;
+use RowOrder;
+
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::mem;
+use std::path::{Path, PathBuf};
+
+/// Creates a `width`x`height` headless context with the default attributes, calls `render`
+/// with the context current, and reads the framebuffer back.
+///
+/// Returns an error if no headless context could be created on this machine, see
+/// `skip_without_backend`.
+pub fn render<F>(width: u32, height: u32, render: F) -> Result<Image, CreationError>
+    where F: FnOnce(&HeadlessContext)
+{
+    render_with(HeadlessRendererBuilder::new(width, height), render)
+}
+
+/// Same as `render`, but builds the context with the given builder.
+pub fn render_with<F>(builder: HeadlessRendererBuilder, render: F) -> Result<Image, CreationError>
+    where F: FnOnce(&HeadlessContext)
+{
+    let context = builder.build()?;
+    unsafe {
+        context.make_current().map_err(|err| {
+            CreationError::OsError(format!("Couldn't make the headless context current: {}", err))
+        })?;
+    }
+
+    render(&context);
+    context.read_pixels(ImageFormat::Rgba8, RowOrder::TopDown).map_err(|err| {
+        CreationError::OsError(format!("Couldn't read the framebuffer back: {}", err))
+    })
+}
+
+/// Returns the value of `result`, or `None` if `result` is an error meaning that this machine
+/// has no headless backend, in which case the test named `test` should return early. The
+/// reason is printed to stderr so that the skipped test doesn't look like it passed.
+///
+/// Panics on any other error, or on a missing backend if the `GLUTIN_REQUIRE_BACKEND`
+/// environment variable is set.
+pub fn skip_without_backend<T>(test: &str, result: Result<T, CreationError>) -> Option<T> {
+    match result {
+        Ok(value) => Some(value),
+        Err(err @ CreationError::NoBackendAvailable(_)) |
+        Err(err @ CreationError::NotSupported) => {
+            if env::var_os("GLUTIN_REQUIRE_BACKEND").is_some() {
+                panic!("{}: no headless backend, but GLUTIN_REQUIRE_BACKEND is set: {}", test,
+                       err);
+            }
+            eprintln!("{}: skipped, no headless backend: {}", test, err);
+            None
+        },
+        Err(err) => panic!("{}: {}", test, err),
+    }
+}
+
+/// Compares `image` to the reference image at `path`.
+///
+/// Two pixels match if none of their channels differ by more than `tolerance`. The reference
+/// is created or updated if the `GLUTIN_UPDATE_REFERENCES` environment variable is set. If the
+/// images don't match, the rendered image and a diff image are written next to the reference.
+pub fn compare<P: AsRef<Path>>(image: &Image, path: P, tolerance: u8)
+                               -> Result<(), CompareError>
+{
+    let path = path.as_ref();
+    let image = to_rgba8(image);
+
+    if env::var_os("GLUTIN_UPDATE_REFERENCES").is_some() {
+        return image.save(path).map_err(CompareError::Io);
+    }
+
+    let reference = match File::open(path) {
+        Ok(file) => read_pam(BufReader::new(file)).map_err(CompareError::Io)?,
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => {
+            let actual = write_actual(&image, path)?;
+            return Err(CompareError::MissingReference { reference: path.to_owned(),
+                                                        actual: actual });
+        },
+        Err(err) => return Err(CompareError::Io(err)),
+    };
+
+    if (reference.width, reference.height) != (image.width, image.height) {
+        let actual = write_actual(&image, path)?;
+        return Err(CompareError::DimensionsMismatch {
+            expected: (reference.width, reference.height),
+            got: (image.width, image.height),
+            actual: actual,
+        });
+    }
+
+    let mut mismatches = 0;
+    let mut max_difference = 0;
+    let mut diff = Vec::with_capacity(image.data.len());
+    for (got, expected) in image.data.chunks(4).zip(reference.data.chunks(4)) {
+        let difference = got.iter().zip(expected)
+                            .map(|(&a, &b)| if a > b { a - b } else { b - a })
+                            .max().unwrap();
+
+        if difference > tolerance {
+            mismatches += 1;
+            max_difference = max_difference.max(difference);
+            diff.extend_from_slice(&[255, 0, 0, 255]);
+        } else {
+            // matching pixels are dimmed so that the mismatches stand out
+            let luma = (got[0] as u32 * 2 + got[1] as u32 * 5 + got[2] as u32) / 8 / 3;
+            diff.extend_from_slice(&[luma as u8, luma as u8, luma as u8, 255]);
+        }
+    }
+
+    if mismatches == 0 {
+        return Ok(());
+    }
+
+    let actual = write_actual(&image, path)?;
+    let diff_path = path.with_extension("diff.png");
+    Image { data: diff, .. image }.save(&diff_path).map_err(CompareError::Io)?;
+
+    Err(CompareError::PixelsMismatch {
+        mismatches: mismatches,
+        max_difference: max_difference,
+        actual: actual,
+        diff: diff_path,
+    })
+}
+
+/// Same as `compare`, but panics with a description of the difference if the images don't
+/// match.
+pub fn assert_matches<P: AsRef<Path>>(image: &Image, path: P, tolerance: u8) {
+    if let Err(err) = compare(image, path.as_ref(), tolerance) {
+        panic!("{}: {}", path.as_ref().display(), err);
+    }
+}
+
+/// Error returned by `compare`.
+#[derive(Debug)]
+pub enum CompareError {
+    /// Reading the reference or writing the outputs failed.
+    Io(io::Error),
+    /// The reference image doesn't exist.
+    MissingReference {
+        reference: PathBuf,
+        /// Where the rendered image was written.
+        actual: PathBuf,
+    },
+    /// The rendered image doesn't have the dimensions of the reference.
+    DimensionsMismatch {
+        expected: (u32, u32),
+        got: (u32, u32),
+        /// Where the rendered image was written.
+        actual: PathBuf,
+    },
+    /// Some pixels differ by more than the tolerance.
+    PixelsMismatch {
+        /// Number of pixels that differ.
+        mismatches: usize,
+        /// Largest difference between two channels.
+        max_difference: u8,
+        /// Where the rendered image was written.
+        actual: PathBuf,
+        /// Where the diff image was written.
+        diff: PathBuf,
+    },
+}
+
+impl fmt::Display for CompareError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            CompareError::Io(ref err) => write!(formatter, "{}: {}", self.description(), err),
+            CompareError::MissingReference { ref reference, ref actual } => {
+                write!(formatter, "{} ({}), set GLUTIN_UPDATE_REFERENCES to create it; the \
+                                   rendered image was written to {}", self.description(),
+                       reference.display(), actual.display())
+            },
+            CompareError::DimensionsMismatch { expected, got, ref actual } => {
+                write!(formatter, "{}: expected {}x{}, got {}x{}; the rendered image was \
+                                   written to {}", self.description(), expected.0, expected.1,
+                       got.0, got.1, actual.display())
+            },
+            CompareError::PixelsMismatch { mismatches, max_difference, ref actual, ref diff } => {
+                write!(formatter, "{}: {} pixels differ, by up to {}; the rendered image was \
+                                   written to {} and the differences to {}", self.description(),
+                       mismatches, max_difference, actual.display(), diff.display())
+            },
+        }
+    }
+}
+
+impl Error for CompareError {
+    fn description(&self) -> &str {
+        match *self {
+            CompareError::Io(_) => "Couldn't read or write an image",
+            CompareError::MissingReference { .. } => "The reference image doesn't exist",
+            CompareError::DimensionsMismatch { .. } => "The dimensions of the rendered image \
+                                                        don't match the reference",
+            CompareError::PixelsMismatch { .. } => "The rendered image doesn't match the \
+                                                    reference",
+        }
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        match *self {
+            CompareError::Io(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+fn write_actual(image: &Image, reference: &Path) -> Result<PathBuf, CompareError> {
+    let path = reference.with_extension("actual.pam");
+    image.save(&path).map_err(CompareError::Io)?;
+    Ok(path)
+}
+
+/// Converts any image to top-down RGBA8, which is what the references are stored as.
+fn to_rgba8(image: &Image) -> Image {
+    let data: Vec<u8> = match image.format {
+        ImageFormat::Rgba8 => image.data.clone(),
+        ImageFormat::Bgra8 => {
+            image.data.chunks(4).flat_map(|p| vec![p[2], p[1], p[0], p[3]]).collect()
+        },
+        ImageFormat::Rgb8 => image.data.chunks(3).flat_map(|p| vec![p[0], p[1], p[2], 255]).collect(),
+        ImageFormat::RgbaF32 => {
+            image.data.chunks(4).map(|bytes| {
+                let mut bits = [0u8; 4];
+                bits.copy_from_slice(bytes);
+                let value = f32::from_bits(unsafe { mem::transmute::<_, u32>(bits) });
+                if value > 0.0 { (value.min(1.0) * 255.0 + 0.5) as u8 } else { 0 }
+            }).collect()
+        },
+    };
+
+    let stride = image.width as usize * 4;
+    let data = match image.row_order {
+        RowOrder::TopDown => data,
+        RowOrder::BottomUp if stride == 0 => data,
+        RowOrder::BottomUp => data.chunks(stride).rev().flat_map(|r| r.iter().cloned()).collect(),
+    };
+
+    Image {
+        width: image.width,
+        height: image.height,
+        stride: stride,
+        format: ImageFormat::Rgba8,
+        row_order: RowOrder::TopDown,
+        data: data,
+    }
+}
+
+/// Reads a PAM file with 8-bit `RGB` or `RGB_ALPHA` samples.
+fn read_pam<R: BufRead>(mut reader: R) -> io::Result<Image> {
+    fn invalid(msg: &str) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, format!("invalid reference image: {}", msg))
+    }
+
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    if line.trim() != "P7" {
+        return Err(invalid("not a PAM file"));
+    }
+
+    let (mut width, mut height, mut depth, mut max_value) = (None, None, None, None);
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(invalid("truncated header"));
+        }
+
+        let mut tokens = line.split_whitespace();
+        let value = |tokens: &mut ::std::str::SplitWhitespace| {
+            tokens.next().and_then(|v| v.parse::<u32>().ok())
+        };
+        match tokens.next() {
+            Some("ENDHDR") => break,
+            Some("WIDTH") => width = value(&mut tokens),
+            Some("HEIGHT") => height = value(&mut tokens),
+            Some("DEPTH") => depth = value(&mut tokens),
+            Some("MAXVAL") => max_value = value(&mut tokens),
+            _ => (),
+        }
+    }
+
+    let (width, height) = match (width, height) {
+        (Some(width), Some(height)) => (width, height),
+        _ => return Err(invalid("missing dimensions")),
+    };
+    if max_value != Some(255) {
+        return Err(invalid("only 8-bit samples are supported"));
+    }
+    let format = match depth {
+        Some(3) => ImageFormat::Rgb8,
+        Some(4) => ImageFormat::Rgba8,
+        _ => return Err(invalid("only RGB and RGB_ALPHA are supported")),
+    };
+
+    let mut data = vec![0; width as usize * height as usize * format.bytes_per_pixel()];
+    reader.read_exact(&mut data)?;
+
+    Ok(to_rgba8(&Image {
+        width: width,
+        height: height,
+        stride: width as usize * format.bytes_per_pixel(),
+        format: format,
+        row_order: RowOrder::TopDown,
+        data: data,
+    }))
+}
@@ -0,0 +1,152 @@
+extern crate glutin;
+
+use glutin::{ConfigBackend, ConfigDescriptor, ConfigId, ConfigRanking, PixelFormat};
+use glutin::{PixelFormatRequirements, ReleaseBehavior};
+
+fn config(id: i32, depth_bits: u8, samples: u16) -> ConfigDescriptor {
+    ConfigDescriptor {
+        id: ConfigId::new(ConfigBackend::Glx, id),
+        pixel_format: PixelFormat {
+            hardware_accelerated: true,
+            color_bits: 24,
+            red_bits: 8,
+            green_bits: 8,
+            blue_bits: 8,
+            alpha_bits: 8,
+            depth_bits: depth_bits,
+            stencil_bits: 8,
+            accum_bits: 0,
+            aux_buffers: 0,
+            stereoscopy: false,
+            double_buffer: true,
+            multisampling: if samples == 0 { None } else { Some(samples) },
+            srgb: false,
+            float_color_buffer: false,
+            transparent: false,
+            release_behavior: ReleaseBehavior::Flush,
+            native_visual_id: None,
+            config_id: Some(ConfigId::new(ConfigBackend::Glx, id)),
+        },
+        float_color_buffer: false,
+        window: true,
+        pbuffer: true,
+    }
+}
+
+#[test]
+fn test_closest_multisampling() {
+    // sorted like glXChooseFBConfig does, with the most samples first
+    let candidates = [config(1, 24, 16), config(2, 24, 8), config(3, 24, 4), config(4, 24, 0)];
+    let reqs = PixelFormatRequirements { multisampling: Some(4), .. Default::default() };
+    assert_eq!(glutin::select_config(&candidates, &reqs), Some(2));
+
+    let reqs = PixelFormatRequirements { multisampling: Some(0), .. Default::default() };
+    assert_eq!(glutin::select_config(&candidates, &reqs), Some(3));
+
+    let reqs = PixelFormatRequirements { multisampling: Some(32), .. Default::default() };
+    assert_eq!(glutin::select_config(&candidates, &reqs), None);
+}
+
+#[test]
+fn test_fewest_extra_bits_and_first_wins_ties() {
+    let candidates = [config(1, 32, 0), config(2, 24, 0), config(3, 24, 0), config(4, 16, 0)];
+    let reqs = PixelFormatRequirements::default();
+    assert_eq!(glutin::select_config(&candidates, &reqs), Some(1));
+}
+
+#[test]
+fn test_software_configs_come_last() {
+    let mut software = config(1, 24, 0);
+    software.pixel_format.hardware_accelerated = false;
+    let candidates = [software, config(2, 32, 0)];
+
+    let reqs = PixelFormatRequirements { hardware_accelerated: None, .. Default::default() };
+    assert_eq!(glutin::select_config(&candidates, &reqs), Some(1));
+
+    let reqs = PixelFormatRequirements { hardware_accelerated: Some(false), .. Default::default() };
+    assert_eq!(glutin::select_config(&candidates, &reqs), Some(0));
+}
+
+#[test]
+fn test_missing_attributes_are_rejected() {
+    let candidates = [config(1, 24, 0)];
+    let rejected = |reqs: PixelFormatRequirements| {
+        assert_eq!(glutin::select_config(&candidates, &reqs), None);
+    };
+
+    let mut software = config(1, 24, 0);
+    software.pixel_format.hardware_accelerated = false;
+    let reqs = PixelFormatRequirements { hardware_accelerated: Some(true), .. Default::default() };
+    assert_eq!(glutin::select_config(&[software], &reqs), None);
+
+    rejected(PixelFormatRequirements { hardware_accelerated: Some(false), .. Default::default() });
+    rejected(PixelFormatRequirements { double_buffer: Some(false), .. Default::default() });
+    rejected(PixelFormatRequirements { stereoscopy: true, .. Default::default() });
+    rejected(PixelFormatRequirements { srgb: true, .. Default::default() });
+}
+
+#[test]
+fn test_matching_attributes_are_accepted() {
+    let mut stereo_srgb = config(1, 24, 0);
+    stereo_srgb.pixel_format.stereoscopy = true;
+    stereo_srgb.pixel_format.srgb = true;
+    let candidates = [stereo_srgb];
+
+    let reqs = PixelFormatRequirements {
+        hardware_accelerated: Some(true),
+        double_buffer: Some(true),
+        stereoscopy: true,
+        srgb: true,
+        .. Default::default()
+    };
+    assert_eq!(glutin::select_config(&candidates, &reqs), Some(0));
+
+    // not requesting an attribute doesn't reject the configs that have it
+    let reqs = PixelFormatRequirements { double_buffer: None, .. Default::default() };
+    assert_eq!(glutin::select_config(&candidates, &reqs), Some(0));
+}
+
+#[test]
+fn test_channel_bits() {
+    let with_channels = |id, (red, green, blue), alpha| {
+        let mut config = config(id, 24, 0);
+        config.pixel_format.red_bits = red;
+        config.pixel_format.green_bits = green;
+        config.pixel_format.blue_bits = blue;
+        config.pixel_format.color_bits = red + green + blue;
+        config.pixel_format.alpha_bits = alpha;
+        config
+    };
+    let candidates = [with_channels(1, (8, 8, 8), 8), with_channels(2, (8, 12, 10), 2),
+                      with_channels(3, (10, 10, 10), 2)];
+
+    let reqs = PixelFormatRequirements {
+        channel_bits: Some((10, 10, 10)),
+        alpha_bits: Some(2),
+        .. Default::default()
+    };
+    assert_eq!(glutin::select_config(&candidates, &reqs), Some(2));
+
+    // without `channel_bits`, only the sum is compared
+    let reqs = PixelFormatRequirements { channel_bits: None, color_bits: Some(30), .. reqs };
+    assert_eq!(glutin::select_config(&candidates, &reqs), Some(1));
+}
+
+#[test]
+fn test_ranking() {
+    let candidates = [config(1, 24, 16), config(2, 32, 8), config(3, 24, 4), config(4, 24, 2)];
+
+    // the most samples, but without a 32-bit depth buffer
+    let reqs = PixelFormatRequirements {
+        ranking: Some(ConfigRanking::new(|config| match config.pixel_format.depth_bits {
+            32 => None,
+            _ => Some(config.pixel_format.multisampling.unwrap_or(0) as u32),
+        })),
+        .. Default::default()
+    };
+    assert_eq!(glutin::select_config(&candidates, &reqs), Some(0));
+
+    // configs that don't meet the requirements are not ranked
+    let reqs = PixelFormatRequirements { multisampling: Some(0), .. reqs };
+    assert_eq!(glutin::select_config(&candidates, &reqs), None);
+}
@@ -0,0 +1,26 @@
+extern crate glutin;
+
+use glutin::{GlBuilder, GlContext, HeadlessRendererBuilder};
+
+// needs an EGL or GLX backend, run with `cargo test -- --ignored`
+#[cfg(target_os = "linux")]
+#[test]
+#[ignore]
+fn test_build_with_listed_config() {
+    let builder = HeadlessRendererBuilder::new(16, 16);
+    let configs = builder.available_configs().unwrap();
+    assert!(!configs.is_empty());
+
+    let config = configs.iter()
+                        .find(|config| !config.float_color_buffer &&
+                                       config.pixel_format.depth_bits > 0)
+                        .unwrap_or(&configs[0]);
+
+    let context = builder.with_config(config.id).build().unwrap();
+    let format = context.get_pixel_format();
+    // the surfaceless backend allocates buffers that are at least as large as the configuration
+    assert!(format.color_bits >= config.pixel_format.color_bits);
+    assert!(format.alpha_bits >= config.pixel_format.alpha_bits);
+    assert!(format.depth_bits >= config.pixel_format.depth_bits);
+    assert!(format.stencil_bits >= config.pixel_format.stencil_bits);
+}
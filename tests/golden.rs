@@ -0,0 +1,68 @@
+#![cfg(feature = "testing")]
+
+extern crate glutin;
+
+mod gl {
+    pub use self::Gles2 as Gl;
+    include!(concat!(env!("OUT_DIR"), "/test_gl_bindings.rs"));
+}
+use glutin::GlContext;
+
+#[test]
+fn test_scissor_clear() {
+    let image = glutin::testing::render(16, 16, |context| {
+        let gl = gl::Gl::load_with(|symbol| context.get_proc_address(symbol) as *const _);
+        unsafe {
+            gl.ClearColor(0.0, 0.0, 1.0, 1.0);
+            gl.Clear(gl::COLOR_BUFFER_BIT);
+            gl.Enable(gl::SCISSOR_TEST);
+            gl.Scissor(0, 0, 8, 8);
+            gl.ClearColor(1.0, 0.0, 0.0, 1.0);
+            gl.Clear(gl::COLOR_BUFFER_BIT);
+            gl.Disable(gl::SCISSOR_TEST);
+        }
+    });
+    let image = match glutin::testing::skip_without_backend("test_scissor_clear", image) {
+        Some(image) => image,
+        None => return,
+    };
+
+    let reference = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/references/scissor.pam");
+    glutin::testing::assert_matches(&image, reference, 0);
+}
+
+#[test]
+fn test_mismatch_writes_outputs() {
+    use glutin::testing::CompareError;
+    use glutin::{Image, ImageFormat, RowOrder};
+
+    let image = Image {
+        width: 16,
+        height: 16,
+        stride: 16 * 4,
+        format: ImageFormat::Rgba8,
+        row_order: RowOrder::TopDown,
+        data: vec![0; 16 * 16 * 4],
+    };
+
+    if std::env::var_os("GLUTIN_UPDATE_REFERENCES").is_some() {
+        return;
+    }
+
+    // work on a copy so that the outputs don't end up in the source tree
+    let reference = std::env::temp_dir().join("glutin-test-mismatch.pam");
+    std::fs::copy(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/references/scissor.pam"),
+                  &reference).unwrap();
+
+    match glutin::testing::compare(&image, &reference, 254) {
+        Err(CompareError::PixelsMismatch { mismatches, max_difference, actual, diff }) => {
+            // every pixel has a channel that differs by 255
+            assert_eq!(mismatches, 256);
+            assert_eq!(max_difference, 255);
+            assert!(actual.exists() && diff.exists());
+        },
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    assert!(glutin::testing::compare(&image, &reference, 255).is_ok());
+}
@@ -59,3 +59,45 @@ fn test_headless() {
         assert_eq!(values[7], 255);
     }
 }
+
+// OSMesa keeps rendering to the old buffer on the thread where the context is current, until
+// the context is made current again
+#[cfg(all(target_os = "linux", feature = "testing"))]
+#[test]
+fn test_osmesa_resize_from_another_thread() {
+    use glutin::os::unix::{HeadlessBackend, HeadlessRendererBuilderExt};
+    use glutin::{ImageFormat, RowOrder};
+    use std::sync::Arc;
+    use std::thread;
+
+    let context = glutin::HeadlessRendererBuilder::new(16, 16)
+        .with_headless_backend(HeadlessBackend::OsMesa)
+        .build();
+    let context = match glutin::testing::skip_without_backend(
+        "test_osmesa_resize_from_another_thread", context)
+    {
+        Some(context) => Arc::new(context),
+        None => return,
+    };
+
+    unsafe { context.make_current().unwrap() };
+    let gl = gl::Gl::load_with(|symbol| context.get_proc_address(symbol) as *const _);
+
+    let resized = context.clone();
+    thread::spawn(move || resized.try_resize(64, 64).unwrap()).join().unwrap();
+
+    unsafe {
+        // still renders to the old buffer, which must not have been freed
+        gl.ClearColor(1.0, 0.0, 0.0, 1.0);
+        gl.Clear(gl::COLOR_BUFFER_BIT);
+        gl.Finish();
+
+        context.make_current().unwrap();
+        gl.ClearColor(0.0, 1.0, 0.0, 1.0);
+        gl.Clear(gl::COLOR_BUFFER_BIT);
+    }
+
+    let image = context.read_pixels(ImageFormat::Rgba8, RowOrder::TopDown).unwrap();
+    assert_eq!((image.width, image.height), (64, 64));
+    assert!(image.data.chunks(4).all(|pixel| pixel == [0, 255, 0, 255]));
+}
@@ -0,0 +1,105 @@
+extern crate glutin;
+
+use glutin::{CreationError, GlBuilder, HeadlessRendererBuilder, PixelFormatRequirements};
+
+#[test]
+fn test_invalid_multisampling() {
+    // rejected before any backend is tried
+    match HeadlessRendererBuilder::new(16, 16).with_multisampling(3).build() {
+        Err(CreationError::InvalidPixelFormatRequirements(_)) => (),
+        Err(err) => panic!("unexpected error: {}", err),
+        Ok(_) => panic!("3x multisampling was accepted"),
+    }
+}
+
+#[test]
+fn test_validate() {
+    assert!(PixelFormatRequirements::default().validate().is_ok());
+
+    let reqs = PixelFormatRequirements {
+        float_color_buffer: true,
+        srgb: true,
+        .. Default::default()
+    };
+    assert!(reqs.validate().is_err());
+
+    let reqs = PixelFormatRequirements {
+        channel_bits: Some((100, 100, 100)),
+        .. Default::default()
+    };
+    assert!(reqs.validate().is_err());
+}
+
+#[test]
+fn test_validate_multisampling() {
+    for &samples in [0, 1, 2, 4, 8, 16, 32768].iter() {
+        let reqs = PixelFormatRequirements {
+            multisampling: Some(samples),
+            .. Default::default()
+        };
+        assert!(reqs.validate().is_ok(), "{} samples were rejected", samples);
+    }
+
+    for &samples in [3, 5, 6, 12, 65535].iter() {
+        let reqs = PixelFormatRequirements {
+            multisampling: Some(samples),
+            .. Default::default()
+        };
+        assert!(reqs.validate().is_err(), "{} samples were accepted", samples);
+    }
+}
+
+#[test]
+fn test_validate_float_srgb() {
+    let float = PixelFormatRequirements {
+        float_color_buffer: true,
+        .. Default::default()
+    };
+    assert!(float.validate().is_ok());
+
+    let srgb = PixelFormatRequirements {
+        srgb: true,
+        .. Default::default()
+    };
+    assert!(srgb.validate().is_ok());
+
+    let both = PixelFormatRequirements {
+        float_color_buffer: true,
+        srgb: true,
+        .. Default::default()
+    };
+    assert!(both.validate().is_err());
+}
+
+#[test]
+fn test_validate_channel_bits() {
+    for &channels in [(85, 85, 85), (255, 0, 0), (0, 0, 255)].iter() {
+        let reqs = PixelFormatRequirements {
+            channel_bits: Some(channels),
+            .. Default::default()
+        };
+        assert!(reqs.validate().is_ok(), "{:?} was rejected", channels);
+    }
+
+    for &channels in [(85, 85, 86), (255, 1, 0), (255, 255, 255)].iter() {
+        let reqs = PixelFormatRequirements {
+            channel_bits: Some(channels),
+            .. Default::default()
+        };
+        assert!(reqs.validate().is_err(), "{:?} was accepted", channels);
+    }
+}
+
+#[test]
+fn test_whole_requirements_are_validated() {
+    let reqs = PixelFormatRequirements {
+        float_color_buffer: true,
+        srgb: true,
+        .. Default::default()
+    };
+    match HeadlessRendererBuilder::new(16, 16).with_pixel_format_requirements(reqs).build() {
+        Err(CreationError::InvalidPixelFormatRequirements(_)) => (),
+        Err(err) => panic!("unexpected error: {}", err),
+        Ok(_) => panic!("a floating-point sRGB color buffer was accepted"),
+    }
+}
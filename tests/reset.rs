@@ -0,0 +1,187 @@
+extern crate glutin;
+
+use glutin::{Api, ContextError, ContextInfo, Extensions, GlContext, PixelFormat, ReleaseBehavior};
+use glutin::{ResetStatus, Robustness};
+
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// the status returned by the fake `glGetGraphicsResetStatus*`, reset after every call like
+// drivers do
+static STATUS: AtomicUsize = AtomicUsize::new(0);
+
+extern "system" fn get_graphics_reset_status() -> u32 {
+    STATUS.swap(0, Ordering::SeqCst) as u32
+}
+
+extern "system" fn get_string(name: u32) -> *const u8 {
+    match name {
+        0x1F02 => b"2.1 Mock\0".as_ptr(),               // GL_VERSION
+        0x1F03 => b"GL_ARB_robustness\0".as_ptr(),      // GL_EXTENSIONS
+        _ => ptr::null(),
+    }
+}
+
+extern "system" fn get_string_without_extensions(name: u32) -> *const u8 {
+    match name {
+        0x1F02 => b"2.1 Mock\0".as_ptr(),
+        _ => ptr::null(),
+    }
+}
+
+/// An OpenGL 2.1 backend whose context can be reset by setting `STATUS`. Like GLX and EGL, it
+/// returns an address for every `glGetGraphicsResetStatus*` function.
+struct MockContext {
+    robustness: Robustness,
+    robustness_extension: bool,
+    current: bool,
+    extensions: Extensions,
+}
+
+impl MockContext {
+    fn new(robustness: Robustness) -> MockContext {
+        MockContext {
+            robustness: robustness,
+            robustness_extension: true,
+            current: true,
+            extensions: Extensions::default(),
+        }
+    }
+}
+
+impl GlContext for MockContext {
+    unsafe fn make_current(&self) -> Result<(), ContextError> {
+        Ok(())
+    }
+
+    fn is_current(&self) -> bool {
+        self.current
+    }
+
+    fn get_proc_address(&self, addr: &str) -> *const () {
+        match addr {
+            "glGetString" if self.robustness_extension => get_string as *const (),
+            "glGetString" => get_string_without_extensions as *const (),
+            _ if addr.starts_with("glGetGraphicsResetStatus") => {
+                get_graphics_reset_status as *const ()
+            },
+            _ => ptr::null(),
+        }
+    }
+
+    fn swap_buffers(&self) -> Result<(), ContextError> {
+        Ok(())
+    }
+
+    fn get_api(&self) -> Api {
+        Api::OpenGl
+    }
+
+    fn get_pixel_format(&self) -> PixelFormat {
+        PixelFormat {
+            hardware_accelerated: false,
+            color_bits: 24,
+            red_bits: 8,
+            green_bits: 8,
+            blue_bits: 8,
+            alpha_bits: 8,
+            depth_bits: 0,
+            stencil_bits: 0,
+            accum_bits: 0,
+            aux_buffers: 0,
+            stereoscopy: false,
+            double_buffer: false,
+            multisampling: None,
+            srgb: false,
+            float_color_buffer: false,
+            transparent: false,
+            release_behavior: ReleaseBehavior::Flush,
+            native_visual_id: None,
+            config_id: None,
+        }
+    }
+
+    fn get_context_info(&self) -> ContextInfo {
+        ContextInfo {
+            version: (2, 1),
+            profile: None,
+            debug: false,
+            forward_compatible: false,
+            robustness: self.robustness,
+        }
+    }
+
+    fn get_platform_extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    fn resize(&self, _: u32, _: u32) {}
+}
+
+// a single test, as the tests would share `STATUS`
+#[test]
+fn test_reset_status() {
+    let context = MockContext::new(Robustness::RobustLoseContextOnReset);
+    assert_eq!(context.get_reset_status().unwrap(), ResetStatus::NoError);
+
+    let statuses = [
+        (0x8253, ResetStatus::GuiltyContextReset),
+        (0x8254, ResetStatus::InnocentContextReset),
+        (0x8255, ResetStatus::UnknownContextReset),
+    ];
+    for &(status, expected) in statuses.iter() {
+        STATUS.store(status, Ordering::SeqCst);
+        assert_eq!(context.get_reset_status().unwrap(), expected);
+        assert_eq!(context.get_reset_status().unwrap(), ResetStatus::NoError);
+    }
+
+    // the function isn't called on contexts that aren't notified of resets, even though its
+    // address is returned
+    for &robustness in [Robustness::NotRobust, Robustness::RobustNoResetNotification].iter() {
+        let context = MockContext::new(robustness);
+        STATUS.store(0x8253, Ordering::SeqCst);
+        assert_eq!(context.get_reset_status().unwrap(), ResetStatus::NoError);
+        assert_eq!(STATUS.load(Ordering::SeqCst), 0x8253);
+    }
+
+    // nor before OpenGL 4.5 without a robustness extension
+    let context = MockContext {
+        robustness_extension: false,
+        .. MockContext::new(Robustness::RobustLoseContextOnReset)
+    };
+    assert_eq!(context.get_reset_status().unwrap(), ResetStatus::NoError);
+    assert_eq!(STATUS.swap(0, Ordering::SeqCst), 0x8253);
+}
+
+#[test]
+fn test_reset_status_not_current() {
+    let context = MockContext {
+        current: false,
+        .. MockContext::new(Robustness::RobustLoseContextOnReset)
+    };
+    match context.get_reset_status() {
+        Err(ContextError::NotCurrent) => (),
+        other => panic!("expected `ContextError::NotCurrent`, got {:?}", other),
+    }
+}
+
+// needs a display, and EGL to create OpenGL ES contexts
+#[cfg(target_os = "linux")]
+#[test]
+#[ignore]
+fn test_recreate_context() {
+    use glutin::{ContextBuilder, EventsLoop, GlRequest, GlWindow, WindowBuilder};
+
+    let events_loop = EventsLoop::new();
+    let window = WindowBuilder::new().with_visibility(false);
+    let context = ContextBuilder::new().with_gl(GlRequest::Specific(Api::OpenGlEs, (2, 0)));
+    let mut gl_window = GlWindow::new(window, context, &events_loop).unwrap();
+    unsafe { gl_window.make_current().unwrap() };
+    gl_window.swap_buffers().unwrap();
+
+    // the new context renders to the surface of the window, which the old context released
+    gl_window.recreate_context().unwrap();
+    unsafe { gl_window.make_current().unwrap() };
+    gl_window.swap_buffers().unwrap();
+    assert_eq!(gl_window.get_reset_status().unwrap(), ResetStatus::NoError);
+}
@@ -0,0 +1,87 @@
+extern crate glutin;
+
+mod gl {
+    pub use self::Gles2 as Gl;
+    include!(concat!(env!("OUT_DIR"), "/test_gl_bindings.rs"));
+}
+use glutin::{GlBuilder, GlContext, HeadlessRendererBuilder, ShareGroup};
+use std::thread;
+
+// Linux may have no headless backend at all, run with `cargo test -- --ignored`
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+#[test]
+#[cfg_attr(target_os = "linux", ignore)]
+fn test_share_group_across_threads() {
+    let group = ShareGroup::new();
+    let main_context = HeadlessRendererBuilder::new(1, 1).with_share_group(&group).build()
+                                                         .unwrap();
+
+    // the texture is created on a worker thread, with a context that is dropped afterwards
+    let worker_group = group.clone();
+    let texture = thread::spawn(move || {
+        let context = HeadlessRendererBuilder::new(1, 1).with_share_group(&worker_group)
+                                                        .build().unwrap();
+        unsafe { context.make_current().unwrap() };
+        let gl = gl::Gl::load_with(|symbol| context.get_proc_address(symbol) as *const _);
+
+        unsafe {
+            let mut texture = 0;
+            gl.GenTextures(1, &mut texture);
+            gl.BindTexture(gl::TEXTURE_2D, texture);
+            gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
+            let pixel: [u8; 4] = [12, 34, 56, 78];
+            gl.TexImage2D(gl::TEXTURE_2D, 0, gl::RGBA as i32, 1, 1, 0, gl::RGBA,
+                          gl::UNSIGNED_BYTE, pixel.as_ptr() as *const _);
+            gl.Finish();
+            texture
+        }
+    }).join().unwrap();
+
+    unsafe { main_context.make_current().unwrap() };
+    let gl = gl::Gl::load_with(|symbol| main_context.get_proc_address(symbol) as *const _);
+
+    unsafe {
+        let mut framebuffer = 0;
+        gl.GenFramebuffers(1, &mut framebuffer);
+        gl.BindFramebuffer(gl::FRAMEBUFFER, framebuffer);
+        gl.FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::TEXTURE_2D, texture, 0);
+        assert_eq!(gl.CheckFramebufferStatus(gl::FRAMEBUFFER), gl::FRAMEBUFFER_COMPLETE);
+
+        let mut pixel = [0u8; 4];
+        gl.ReadPixels(0, 0, 1, 1, gl::RGBA, gl::UNSIGNED_BYTE, pixel.as_mut_ptr() as *mut _);
+        assert_eq!(pixel, [12, 34, 56, 78]);
+    }
+}
+
+// the group keeps the first context alive, along with the objects of the group
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+#[test]
+#[cfg_attr(target_os = "linux", ignore)]
+fn test_share_group_outlives_first_context() {
+    let group = ShareGroup::new();
+
+    let texture = {
+        let context = HeadlessRendererBuilder::new(1, 1).with_share_group(&group).build()
+                                                        .unwrap();
+        unsafe { context.make_current().unwrap() };
+        let gl = gl::Gl::load_with(|symbol| context.get_proc_address(symbol) as *const _);
+
+        unsafe {
+            let mut texture = 0;
+            gl.GenTextures(1, &mut texture);
+            gl.BindTexture(gl::TEXTURE_2D, texture);
+            gl.Finish();
+            texture
+        }
+    };
+
+    let worker_group = group.clone();
+    drop(group);
+    thread::spawn(move || {
+        let context = HeadlessRendererBuilder::new(1, 1).with_share_group(&worker_group)
+                                                        .build().unwrap();
+        unsafe { context.make_current().unwrap() };
+        let gl = gl::Gl::load_with(|symbol| context.get_proc_address(symbol) as *const _);
+        assert_eq!(unsafe { gl.IsTexture(texture) }, gl::TRUE);
+    }).join().unwrap();
+}
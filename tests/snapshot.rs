@@ -0,0 +1,57 @@
+#![cfg(feature = "snapshot")]
+
+extern crate glutin;
+
+use glutin::{Image, ImageFormat, RowOrder};
+
+fn test_image(row_order: RowOrder) -> Image {
+    // 2x2, red and green on the first row
+    let rows: [[u8; 8]; 2] = [[255, 0, 0, 255, 0, 255, 0, 128], [0, 0, 255, 255, 0, 0, 0, 0]];
+    let data = match row_order {
+        RowOrder::TopDown => [rows[0], rows[1]].concat(),
+        RowOrder::BottomUp => [rows[1], rows[0]].concat(),
+    };
+
+    Image {
+        width: 2,
+        height: 2,
+        stride: 8,
+        format: ImageFormat::Rgba8,
+        row_order: row_order,
+        data: data,
+    }
+}
+
+#[test]
+fn test_ppm_flips_and_drops_alpha() {
+    let mut ppm = Vec::new();
+    test_image(RowOrder::BottomUp).write_ppm(&mut ppm).unwrap();
+
+    let mut expected = b"P6\n2 2\n255\n".to_vec();
+    expected.extend_from_slice(&[255, 0, 0, 0, 255, 0, 0, 0, 255, 0, 0, 0]);
+    assert_eq!(ppm, expected);
+}
+
+#[test]
+fn test_pam_keeps_alpha() {
+    let mut pam = Vec::new();
+    test_image(RowOrder::TopDown).write_pam(&mut pam).unwrap();
+
+    let header = b"P7\nWIDTH 2\nHEIGHT 2\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n";
+    assert_eq!(&pam[.. header.len()], &header[..]);
+    assert_eq!(&pam[header.len() ..], &test_image(RowOrder::TopDown).data[..]);
+}
+
+#[test]
+fn test_png_structure() {
+    let mut png = Vec::new();
+    test_image(RowOrder::TopDown).write_png(&mut png).unwrap();
+
+    assert_eq!(&png[.. 8], b"\x89PNG\r\n\x1a\n");
+    // IHDR: 2x2, 8 bits, RGBA
+    assert_eq!(&png[12 .. 16], b"IHDR");
+    assert_eq!(&png[16 .. 29], &[0, 0, 0, 2, 0, 0, 0, 2, 8, 6, 0, 0, 0]);
+    // crc of the IHDR chunk
+    assert_eq!(&png[29 .. 33], &[0x72, 0xb6, 0x0d, 0x24]);
+    assert_eq!(&png[png.len() - 12 ..], b"\0\0\0\0IEND\xae\x42\x60\x82");
+}